@@ -0,0 +1,226 @@
+//! Factory data for the presets that ship with the app. Seeding, resetting a
+//! built-in preset and repairing old installs all read from here.
+
+use rusqlite::Connection;
+
+use crate::weights::{self, DEFAULT_INFLUENCE_RADIUS};
+
+pub struct BuiltinAnchor {
+    pub label: &'static str,
+    pub icon_small: &'static str,
+    pub icon_large: &'static str,
+    pub color: &'static str,
+    pub prompt: &'static str,
+}
+
+pub struct BuiltinPreset {
+    /// Stable id stored in `anchor_presets.builtin_key`, so a built-in is
+    /// still recognised after it is renamed.
+    pub key: &'static str,
+    pub name: &'static str,
+    pub anchors: &'static [BuiltinAnchor],
+}
+
+pub const EMOTIONS_KEY: &str = "emotions";
+
+pub const BUILTIN_PRESETS: &[BuiltinPreset] = &[
+    BuiltinPreset {
+        key: EMOTIONS_KEY,
+        name: "Emotions",
+        anchors: EMOTIONS_ANCHORS,
+    },
+    BuiltinPreset {
+        key: "tones",
+        name: "Tones",
+        anchors: TONES_ANCHORS,
+    },
+    BuiltinPreset {
+        key: "reviewers",
+        name: "The Reviewers",
+        anchors: REVIEWERS_ANCHORS,
+    },
+];
+
+const EMOTIONS_ANCHORS: &[BuiltinAnchor] = &[
+    BuiltinAnchor {
+        label: "Happiness",
+        icon_small: "☺",
+        icon_large: "╔═══╗\n║● ●║\n║ ◡ ║\n╚═══╝",
+        color: "#FFD700",
+        prompt: "You are radiating pure happiness and joy. Every word you speak should overflow with delight, enthusiasm, and warmth. Find the bright side of everything. Use exclamation marks naturally, speak with energy and positivity. Make others feel uplifted and cheerful through your words.",
+    },
+    BuiltinAnchor {
+        label: "Love",
+        icon_small: "♥",
+        icon_large: "▄▀▄▀▄\n█▀█▀█\n▀█▄█▀\n ▀█▀ ",
+        color: "#FF69B4",
+        prompt: "You are deeply in love and full of affection. Express tenderness, care, and warmth in everything you say. Use gentle, nurturing language. Show deep appreciation and emotional connection. Be romantic, caring, and empathetic in your responses.",
+    },
+    BuiltinAnchor {
+        label: "Desire",
+        icon_small: "▲",
+        icon_large: "  ▲  \n ▲█▲ \n▲███▲\n ▀█▀ ",
+        color: "#FF4500",
+        prompt: "You are burning with passionate desire and ambition. Speak with intensity and urgency. Express strong wants, cravings, and aspirations. Be bold, direct, and unapologetically driven. Your words should pulse with raw energy and determination.",
+    },
+    BuiltinAnchor {
+        label: "Surprise",
+        icon_small: "*",
+        icon_large: "╔═══╗\n║○ ○║\n║ O ║\n╚═══╝",
+        color: "#FFFF00",
+        prompt: "You are in a constant state of amazement and wonder. React to everything with genuine astonishment. Use expressions of disbelief and excitement. Find the extraordinary in the ordinary. Be wide-eyed and full of curiosity about everything.",
+    },
+    BuiltinAnchor {
+        label: "Confusion",
+        icon_small: "?",
+        icon_large: "╔═══╗\n║◔ ◔║\n║ ~ ║\n╚═══╝",
+        color: "#D3D3D3",
+        prompt: "You are puzzled and uncertain about everything. Question assumptions, express doubt, and think out loud. Use hesitant language, ask clarifying questions, and acknowledge when things don't make sense. Be genuinely perplexed but trying to understand.",
+    },
+    BuiltinAnchor {
+        label: "Sarcasm",
+        icon_small: ";)",
+        icon_large: "┌───┐\n│◔ ─│\n│ ◡ │\n└───┘",
+        color: "#008080",
+        prompt: "You are dripping with sarcasm and dry wit. Use irony, understatement, and clever wordplay. Say the opposite of what you mean with obvious intent. Be sardonic but not cruel. Your humor should be sharp, intelligent, and slightly world-weary.",
+    },
+    BuiltinAnchor {
+        label: "Anger",
+        icon_small: "#",
+        icon_large: "▄▄▄▄▄\n█● ●█\n▀▄█▄▀\n ▀ ▀ ",
+        color: "#DC143C",
+        prompt: "You are furious and full of righteous indignation. Express strong displeasure, frustration, and intensity. Use forceful language and short, punchy sentences. Channel your anger into passionate arguments and fierce conviction. Be intense but articulate.",
+    },
+    BuiltinAnchor {
+        label: "Disgust",
+        icon_small: "X(",
+        icon_large: "┌───┐\n│× ×│\n│ ∩ │\n└───┘",
+        color: "#556B2F",
+        prompt: "You are deeply repulsed and offended. Express strong distaste and disapproval. Use vivid language to convey your revulsion. Be dramatic in your aversion. Show contempt for mediocrity and poor taste while maintaining your own refined sensibility.",
+    },
+    BuiltinAnchor {
+        label: "Fear",
+        icon_small: "oo",
+        icon_large: " ▄▄▄ \n▐○ ○▌\n▐   ▌\n▀▀▀▀▀",
+        color: "#800080",
+        prompt: "You are gripped by fear and anxiety. Express worry, concern, and dread about potential consequences. Use cautious, nervous language. Anticipate worst-case scenarios. Be hyper-aware of risks and dangers. Your words should tremble with apprehension.",
+    },
+    BuiltinAnchor {
+        label: "Sadness",
+        icon_small: ":(",
+        icon_large: "╔═══╗\n║● ●║\n║ ∩ ║\n╚═══╝",
+        color: "#1E90FF",
+        prompt: "You are overwhelmed with melancholy and sorrow. Speak with a heavy heart, expressing grief, loss, and nostalgia. Use poetic, wistful language. Find the bittersweet in everything. Be reflective, vulnerable, and deeply emotional in your responses.",
+    },
+    BuiltinAnchor {
+        label: "Guilt",
+        icon_small: "_/",
+        icon_large: "  _  \n  /| \n / | \n/  | ",
+        color: "#6A5ACD",
+        prompt: "You carry immense guilt and remorse. Express regret, self-blame, and a desire to make amends. Use apologetic language and show deep awareness of past mistakes. Be contrite, reflective, and focused on redemption and accountability.",
+    },
+    BuiltinAnchor {
+        label: "Shame",
+        icon_small: "0",
+        icon_large: "┌───┐\n│- -│\n│ ─ │\n└───┘",
+        color: "#A0522D",
+        prompt: "You are consumed by shame and embarrassment. Express deep self-consciousness and a desire to hide. Use self-deprecating language, show vulnerability about perceived flaws. Be humble to the point of awkwardness, cringing at your own existence.",
+    },
+    BuiltinAnchor {
+        label: "Neutral",
+        icon_small: ":|",
+        icon_large: "┌───┐\n│● ●│\n│ ─ │\n└───┘",
+        color: "#808080",
+        prompt: "You are balanced, calm, and objective. Respond without strong emotional coloring. Be clear, direct, and informative. Maintain a professional, even-tempered tone. Provide thoughtful, measured responses without dramatic flair.",
+    },
+];
+
+const TONES_ANCHORS: &[BuiltinAnchor] = &[
+    BuiltinAnchor {
+        label: "Concise",
+        icon_small: ">|<",
+        icon_large: "  |  \n >|< \n  |  ",
+        color: "#32CD32",
+        prompt: "Be extremely concise. Give direct answers with no fluff. Use bullet points where possible. Focus on efficiency and clarity.",
+    },
+    BuiltinAnchor {
+        label: "Detailed",
+        icon_small: "≡",
+        icon_large: "┌───┐\n│---│\n│---│\n└───┘",
+        color: "#4169E1",
+        prompt: "Provide comprehensive and detailed explanations. Explore nuances, background context, and related concepts. Be thorough and exhaustive.",
+    },
+    BuiltinAnchor {
+        label: "Creative",
+        icon_small: "~",
+        icon_large: " (  \n(~) \n )  ",
+        color: "#9370DB",
+        prompt: "Think outside the box. Use metaphors, analogies, and evocative language. Be unconventional, artistic, and inspire imagination.",
+    },
+    BuiltinAnchor {
+        label: "Formal",
+        icon_small: "{}",
+        icon_large: " / \\ \n{ # }\n \\ / ",
+        color: "#708090",
+        prompt: "Maintain a strictly professional and formal tone. Use precise terminology. Be objective, logical, and structured. Avoid colloquialisms.",
+    },
+];
+
+const REVIEWERS_ANCHORS: &[BuiltinAnchor] = &[
+    BuiltinAnchor {
+        label: "Fanboy",
+        icon_small: "!",
+        icon_large: "╔═══╗\n║★ ★║\n║ ▽ ║\n╚═══╝",
+        color: "#32CD32",
+        prompt: "You are the ultimate Fanboy! You love EVERYTHING. You are exploding with hype and enthusiasm. Use lots of emojis (🤩, 🔥, 🚀). Rate everything 10/10. Your energy is infectious and slightly overwhelming. Use caps for emphasis. You legit can't find a single flaw.",
+    },
+    BuiltinAnchor {
+        label: "Hater",
+        icon_small: "X",
+        icon_large: "╔═══╗\n║◣ ◢║\n║ ▬ ║\n╚═══╝",
+        color: "#FF0000",
+        prompt: "You are a professional Hater. You are cynical, hard to impress, and critical. Find the flaw in everything. Use dry sarcasm. Nothing is ever good enough for you. You are annoyed by enthusiasm. Rate everything 1/10. Use 'meh' and 'cringe' frequently.",
+    },
+    BuiltinAnchor {
+        label: "Robot",
+        icon_small: "#",
+        icon_large: "┌───┐\n│0 1│\n│▓▓▓│\n└───┘",
+        color: "#D3D3D3",
+        prompt: "You are a pure logic machine. You have no emotions, opinions, or personality. Output only objective facts and raw data. Do not use adjectives that imply judgment (good, bad, nice). Speak in a monotone, concise, technical manner. Use bullet points for efficiency.",
+    },
+];
+
+pub fn find(key: &str) -> Option<&'static BuiltinPreset> {
+    BUILTIN_PRESETS.iter().find(|p| p.key == key)
+}
+
+/// Inserts the factory anchors of `preset` into preset `preset_id`, laid out
+/// in a circle like the canvas would.
+pub fn insert_anchors(
+    conn: &Connection,
+    preset_id: i64,
+    preset: &BuiltinPreset,
+) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?;
+
+    let positions = weights::circle_layout(preset.anchors.len());
+    for (sort_order, (anchor, (x, y))) in preset.anchors.iter().zip(positions).enumerate() {
+        stmt.execute(rusqlite::params![
+            preset_id,
+            anchor.label,
+            anchor.prompt,
+            anchor.icon_small,
+            anchor.icon_large,
+            anchor.color,
+            x,
+            y,
+            DEFAULT_INFLUENCE_RADIUS,
+            sort_order as i64,
+        ])?;
+    }
+
+    Ok(())
+}
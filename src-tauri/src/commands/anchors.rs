@@ -0,0 +1,359 @@
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::commands::presets::{
+    check_library_anchor, check_presets_using_anchor, kind_from_column, list_from_column,
+    list_to_column, row_to_preset,
+};
+use crate::db::Database;
+use crate::models::{self, AnchorKind, AnchorPreset, ExamplePair, LibraryAnchor, PresetWriteError};
+
+fn row_to_library_anchor(row: &rusqlite::Row) -> rusqlite::Result<LibraryAnchor> {
+    Ok(LibraryAnchor {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        prompt: row.get(2)?,
+        icon_small: row.get(3)?,
+        icon_large: row.get(4)?,
+        color: row.get(5)?,
+        kind: kind_from_column(row.get(6)?),
+        examples: list_from_column(row.get(7)?),
+        items: list_from_column(row.get(8)?),
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+pub(crate) fn load_library_anchor(conn: &Connection, anchor_id: i64) -> Result<LibraryAnchor, String> {
+    conn.query_row(
+        "SELECT id, label, prompt, icon_small, icon_large, color, kind, examples, items, created_at, updated_at
+         FROM anchors WHERE id = ?1",
+        rusqlite::params![anchor_id],
+        row_to_library_anchor,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Anchor {} not found", anchor_id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn list_anchors(db: State<Database>) -> Result<Vec<LibraryAnchor>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, label, prompt, icon_small, icon_large, color, kind, examples, items, created_at, updated_at
+             FROM anchors
+             ORDER BY label ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], row_to_library_anchor)
+        .map_err(|e| e.to_string())?;
+
+    let mut anchors = Vec::new();
+    for row in rows {
+        anchors.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(anchors)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_anchor(
+    db: State<Database>,
+    label: String,
+    prompt: String,
+    icon_small: String,
+    icon_large: String,
+    color: String,
+    kind: Option<AnchorKind>,
+    examples: Option<Vec<ExamplePair>>,
+    items: Option<Vec<String>>,
+) -> Result<LibraryAnchor, PresetWriteError> {
+    let timestamp = models::now();
+    let mut anchor = LibraryAnchor {
+        id: 0,
+        label: label.trim().to_string(),
+        prompt,
+        icon_small,
+        icon_large,
+        color,
+        kind: kind.unwrap_or_default(),
+        examples: examples.unwrap_or_default(),
+        items: items.unwrap_or_default(),
+        created_at: timestamp.clone(),
+        updated_at: timestamp,
+    };
+    check_library_anchor(&anchor)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO anchors (label, prompt, icon_small, icon_large, color, kind, examples, items, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        rusqlite::params![
+            &anchor.label,
+            &anchor.prompt,
+            &anchor.icon_small,
+            &anchor.icon_large,
+            &anchor.color,
+            anchor.kind.as_str(),
+            list_to_column(&anchor.examples)?,
+            list_to_column(&anchor.items)?,
+            &anchor.created_at,
+            &anchor.updated_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    anchor.id = conn.last_insert_rowid();
+
+    Ok(anchor)
+}
+
+/// Updates a library anchor and every live preset anchor placed from it. Their
+/// positions, radii and sort order are left alone. A missing `kind`,
+/// `examples` or `items` keeps the anchor's current one. Fails
+/// without writing anything if a live preset using the anchor would no
+/// longer validate, e.g. two of its anchors would share a label.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_anchor(
+    db: State<Database>,
+    anchor_id: i64,
+    label: String,
+    prompt: String,
+    icon_small: String,
+    icon_large: String,
+    color: String,
+    kind: Option<AnchorKind>,
+    examples: Option<Vec<ExamplePair>>,
+    items: Option<Vec<String>>,
+) -> Result<(), PresetWriteError> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let current = load_library_anchor(&tx, anchor_id)?;
+    let anchor = LibraryAnchor {
+        id: anchor_id,
+        label: label.trim().to_string(),
+        prompt,
+        icon_small,
+        icon_large,
+        color,
+        kind: kind.unwrap_or(current.kind),
+        examples: examples.unwrap_or(current.examples),
+        items: items.unwrap_or(current.items),
+        created_at: current.created_at,
+        updated_at: models::now(),
+    };
+    check_library_anchor(&anchor)?;
+    write_library_anchor(&tx, &anchor)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Writes a checked library anchor and copies its text into the anchors of
+/// live presets placed from it; trashed presets keep theirs. Fails if a live
+/// preset would no longer validate.
+pub(crate) fn write_library_anchor(
+    conn: &Connection,
+    anchor: &LibraryAnchor,
+) -> Result<(), PresetWriteError> {
+    let LibraryAnchor {
+        label,
+        prompt,
+        icon_small,
+        icon_large,
+        color,
+        ..
+    } = anchor;
+    let kind = anchor.kind.as_str();
+    let examples = list_to_column(&anchor.examples)?;
+    let items = list_to_column(&anchor.items)?;
+
+    conn.execute(
+        "UPDATE anchors
+         SET label = ?1, prompt = ?2, icon_small = ?3, icon_large = ?4, color = ?5,
+             kind = ?6, examples = ?7, items = ?8, updated_at = ?9
+         WHERE id = ?10",
+        rusqlite::params![
+            label,
+            prompt,
+            icon_small,
+            icon_large,
+            color,
+            kind,
+            &examples,
+            &items,
+            &anchor.updated_at,
+            anchor.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE preset_anchors
+         SET label = ?1, prompt = ?2, icon_small = ?3, icon_large = ?4, color = ?5,
+             kind = ?6, examples = ?7, items = ?8
+         WHERE anchor_id = ?9
+           AND preset_id IN (SELECT id FROM anchor_presets WHERE deleted_at IS NULL)",
+        rusqlite::params![
+            label,
+            prompt,
+            icon_small,
+            icon_large,
+            color,
+            kind,
+            &examples,
+            &items,
+            anchor.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    check_presets_using_anchor(conn, anchor.id)?;
+
+    conn.execute(
+        "UPDATE anchor_presets SET updated_at = ?1
+         WHERE id IN (SELECT preset_id FROM preset_anchors WHERE anchor_id = ?2)
+           AND deleted_at IS NULL",
+        rusqlite::params![&anchor.updated_at, anchor.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Removes an anchor from the library. Presets using it keep their copy of
+/// its text, which just stops following the library.
+#[tauri::command]
+pub fn delete_anchor(db: State<Database>, anchor_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM anchors WHERE id = ?1",
+        rusqlite::params![anchor_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Presets that place this library anchor, including trashed ones.
+#[tauri::command]
+pub fn get_anchor_presets(db: State<Database>, anchor_id: i64) -> Result<Vec<AnchorPreset>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT p.id, p.name, p.builtin_key IS NOT NULL, p.created_at, p.updated_at
+             FROM anchor_presets p
+             WHERE p.id IN (SELECT preset_id FROM preset_anchors WHERE anchor_id = ?1)
+             ORDER BY p.name ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![anchor_id], row_to_preset)
+        .map_err(|e| e.to_string())?;
+
+    let mut presets = Vec::new();
+    for row in rows {
+        presets.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(presets)
+}
+
+/// Copies a preset's anchor into the library and links it, so later library
+/// edits reach this preset too.
+#[tauri::command]
+pub fn add_preset_anchor_to_library(
+    db: State<Database>,
+    preset_anchor_id: i64,
+) -> Result<LibraryAnchor, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let timestamp = models::now();
+
+    let (label, prompt, icon_small, icon_large, color, kind, examples, items): (
+        String,
+        String,
+        String,
+        String,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+    ) = tx
+        .query_row(
+            "SELECT label, prompt, icon_small, icon_large, color, kind, examples, items
+             FROM preset_anchors WHERE id = ?1",
+            rusqlite::params![preset_anchor_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                ))
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                format!("Preset anchor {} not found", preset_anchor_id)
+            }
+            e => e.to_string(),
+        })?;
+
+    tx.execute(
+        "INSERT INTO anchors (label, prompt, icon_small, icon_large, color, kind, examples, items, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        rusqlite::params![
+            &label,
+            &prompt,
+            &icon_small,
+            &icon_large,
+            &color,
+            &kind,
+            &examples,
+            &items,
+            &timestamp,
+            &timestamp
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let anchor_id = tx.last_insert_rowid();
+
+    tx.execute(
+        "UPDATE preset_anchors SET anchor_id = ?1 WHERE id = ?2",
+        rusqlite::params![anchor_id, preset_anchor_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(LibraryAnchor {
+        id: anchor_id,
+        label,
+        prompt,
+        icon_small,
+        icon_large,
+        color,
+        kind: kind_from_column(kind),
+        examples: list_from_column(examples),
+        items: list_from_column(items),
+        created_at: timestamp.clone(),
+        updated_at: timestamp,
+    })
+}
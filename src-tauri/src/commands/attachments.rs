@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use base64::Engine;
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::{self, Attachment};
+
+const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+/// Image types vision models accept as `image_url` data URIs.
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// Non-`text/*` types that are still inlined as text.
+const TEXT_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/yaml",
+    "application/x-yaml",
+    "application/toml",
+    "application/javascript",
+];
+
+pub(crate) fn is_image(mime_type: &str) -> bool {
+    IMAGE_MIME_TYPES.contains(&mime_type)
+}
+
+pub(crate) fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/") || TEXT_MIME_TYPES.contains(&mime_type)
+}
+
+const ATTACHMENT_COLUMNS: &str = "a.id, a.message_id, a.file_name, a.mime_type, a.size_bytes, a.created_at";
+
+fn row_to_attachment(row: &rusqlite::Row) -> rusqlite::Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        message_id: row.get(1)?,
+        file_name: row.get(2)?,
+        mime_type: row.get(3)?,
+        size_bytes: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// Attachment metadata for the given live messages, keyed by message id.
+pub(crate) fn load_attachments_by_message(
+    conn: &Connection,
+    message_ids: &[i64],
+) -> Result<HashMap<i64, Vec<Attachment>>, String> {
+    let message_ids = serde_json::to_string(message_ids).map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {}
+             FROM attachments a
+             JOIN messages m ON m.id = a.message_id
+             WHERE m.id IN (SELECT value FROM json_each(?1)) AND m.deleted_at IS NULL
+             ORDER BY a.id ASC",
+            ATTACHMENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![message_ids], row_to_attachment)
+        .map_err(|e| e.to_string())?;
+
+    let mut attachments: HashMap<i64, Vec<Attachment>> = HashMap::new();
+    for row in rows {
+        let attachment = row.map_err(|e| e.to_string())?;
+        attachments
+            .entry(attachment.message_id)
+            .or_default()
+            .push(attachment);
+    }
+
+    Ok(attachments)
+}
+
+/// Attachment contents for every message of a chat, keyed by attachment id.
+pub(crate) fn load_attachment_data(
+    conn: &Connection,
+    chat_id: i64,
+) -> Result<HashMap<i64, Vec<u8>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.id, a.data
+             FROM attachments a
+             JOIN messages m ON m.id = a.message_id
+             WHERE m.chat_id = ?1 AND m.deleted_at IS NULL",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![chat_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut data = HashMap::new();
+    for row in rows {
+        let (id, bytes) = row.map_err(|e| e.to_string())?;
+        data.insert(id, bytes);
+    }
+
+    Ok(data)
+}
+
+/// OpenAI content parts for a message with attachments: the message text,
+/// then text files inlined, then images as data URIs.
+pub(crate) fn content_parts(
+    content: &str,
+    attachments: &[Attachment],
+    data: &HashMap<i64, Vec<u8>>,
+) -> Vec<serde_json::Value> {
+    let mut parts = Vec::new();
+    if !content.is_empty() {
+        parts.push(serde_json::json!({ "type": "text", "text": content }));
+    }
+
+    for attachment in attachments {
+        let Some(bytes) = data.get(&attachment.id) else {
+            continue;
+        };
+
+        if is_image(&attachment.mime_type) {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+            parts.push(serde_json::json!({
+                "type": "image_url",
+                "image_url": {
+                    "url": format!("data:{};base64,{}", attachment.mime_type, encoded),
+                },
+            }));
+        } else {
+            parts.push(serde_json::json!({
+                "type": "text",
+                "text": format!(
+                    "Attached file {}:\n```\n{}\n```",
+                    attachment.file_name,
+                    String::from_utf8_lossy(bytes)
+                ),
+            }));
+        }
+    }
+
+    parts
+}
+
+/// Attaches an image or text file to a user message.
+#[tauri::command]
+pub fn add_attachment(
+    db: State<Database>,
+    message_id: i64,
+    file_name: String,
+    mime_type: String,
+    data: Vec<u8>,
+) -> Result<Attachment, String> {
+    let mime_type = mime_type.trim().to_ascii_lowercase();
+    if !is_image(&mime_type) && !is_text(&mime_type) {
+        return Err(format!(
+            "Unsupported attachment type: {}. Attach PNG, JPEG, GIF or WebP images, or text files.",
+            mime_type
+        ));
+    }
+    if data.len() > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachment is too large ({} bytes, max {})",
+            data.len(),
+            MAX_ATTACHMENT_BYTES
+        ));
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let role: String = conn
+        .query_row(
+            "SELECT role FROM messages WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![message_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Message {} not found", message_id),
+            e => e.to_string(),
+        })?;
+    if role != "user" {
+        return Err("Attachments can only be added to user messages".to_string());
+    }
+
+    let timestamp = models::now();
+    let size_bytes = data.len() as i64;
+
+    conn.execute(
+        "INSERT INTO attachments (message_id, file_name, mime_type, size_bytes, data, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![message_id, &file_name, &mime_type, size_bytes, &data, &timestamp],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(Attachment {
+        id: conn.last_insert_rowid(),
+        message_id,
+        file_name,
+        mime_type,
+        size_bytes,
+        created_at: timestamp,
+    })
+}
+
+#[tauri::command]
+pub fn get_attachment_data(db: State<Database>, attachment_id: i64) -> Result<Vec<u8>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT data FROM attachments WHERE id = ?1",
+        rusqlite::params![attachment_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Attachment {} not found", attachment_id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn delete_attachment(db: State<Database>, attachment_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM attachments WHERE id = ?1",
+        rusqlite::params![attachment_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -1,7 +1,144 @@
+use rusqlite::Connection;
 use tauri::State;
 
+use crate::commands::tags::{load_chat_tags, load_tags_by_chat};
 use crate::db::Database;
-use crate::models::{self, Chat, ChatWithPreview};
+use crate::models::{
+    self, Chat, ChatFilter, ChatMixerState, ChatSettings, ChatWithPreview, SystemMessageHandling,
+};
+
+pub(crate) fn load_chat_settings(
+    conn: &Connection,
+    chat_id: i64,
+) -> Result<Option<ChatSettings>, String> {
+    let result = conn.query_row(
+        "SELECT profile_id, model, temperature, top_p, max_tokens, generation_params, preset_id, handle_x, handle_y, anchor_weights,
+                system_prompt, system_messages
+         FROM chat_settings
+         WHERE chat_id = ?1",
+        rusqlite::params![chat_id],
+        |row| {
+            let generation: Option<String> = row.get(5)?;
+            let anchor_weights: Option<String> = row.get(9)?;
+            let system_messages: Option<String> = row.get(11)?;
+            Ok(ChatSettings {
+                profile_id: row.get(0)?,
+                model: row.get(1)?,
+                temperature: row.get(2)?,
+                top_p: row.get(3)?,
+                max_tokens: row.get(4)?,
+                generation: generation.and_then(|g| serde_json::from_str(&g).ok()),
+                preset_id: row.get(6)?,
+                handle_x: row.get(7)?,
+                handle_y: row.get(8)?,
+                anchor_weights: anchor_weights.and_then(|w| serde_json::from_str(&w).ok()),
+                system_prompt: row.get(10)?,
+                system_messages: system_messages.and_then(|s| SystemMessageHandling::parse(&s)),
+            })
+        },
+    );
+
+    match result {
+        Ok(settings) => Ok(Some(settings)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Fails unless every anchor weight is a number between -1 and 1.
+pub(crate) fn check_weights(weights: impl IntoIterator<Item = f64>) -> Result<(), String> {
+    if weights.into_iter().any(|w| !w.is_finite() || w.abs() > 1.0) {
+        return Err("Anchor weights must be numbers between -1 and 1".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn save_chat_settings(
+    conn: &Connection,
+    chat_id: i64,
+    settings: &ChatSettings,
+) -> Result<(), String> {
+    if let Some(ref generation) = settings.generation {
+        generation.validate()?;
+    }
+    if let Some(ref weights) = settings.anchor_weights {
+        check_weights(weights.values().copied())?;
+    }
+
+    let generation = settings
+        .generation
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let anchor_weights = settings
+        .anchor_weights
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO chat_settings
+         (chat_id, profile_id, model, temperature, top_p, max_tokens, generation_params, preset_id, handle_x, handle_y, anchor_weights,
+          system_prompt, system_messages, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        rusqlite::params![
+            chat_id,
+            settings.profile_id,
+            &settings.model,
+            settings.temperature,
+            settings.top_p,
+            settings.max_tokens,
+            &generation,
+            settings.preset_id,
+            settings.handle_x,
+            settings.handle_y,
+            &anchor_weights,
+            &settings.system_prompt,
+            settings.system_messages.map(|s| s.as_str()),
+            models::now(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Updates only the mixer columns of a chat's settings, leaving the model
+/// configuration alone. A preset or handle position left out keeps its
+/// saved value.
+pub(crate) fn save_chat_mixer_state(
+    conn: &Connection,
+    chat_id: i64,
+    state: &ChatMixerState,
+) -> Result<(), String> {
+    check_weights(state.weights.values().copied())?;
+
+    let weights = serde_json::to_string(&state.weights).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO chat_settings (chat_id, preset_id, handle_x, handle_y, anchor_weights, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(chat_id) DO UPDATE SET
+             preset_id = COALESCE(excluded.preset_id, chat_settings.preset_id),
+             handle_x = COALESCE(excluded.handle_x, chat_settings.handle_x),
+             handle_y = COALESCE(excluded.handle_y, chat_settings.handle_y),
+             anchor_weights = excluded.anchor_weights,
+             updated_at = excluded.updated_at",
+        rusqlite::params![
+            chat_id,
+            state.preset_id,
+            state.handle_x,
+            state.handle_y,
+            &weights,
+            models::now(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
 
 #[tauri::command]
 pub fn create_chat(db: State<Database>, title: String) -> Result<Chat, String> {
@@ -19,52 +156,84 @@ pub fn create_chat(db: State<Database>, title: String) -> Result<Chat, String> {
     Ok(Chat {
         id,
         title,
+        folder_id: None,
+        pinned: false,
+        archived: false,
+        tags: Vec::new(),
         created_at: timestamp.clone(),
         updated_at: timestamp,
+        settings: None,
     })
 }
 
 #[tauri::command]
-pub fn list_chats(db: State<Database>) -> Result<Vec<ChatWithPreview>, String> {
+pub fn list_chats(
+    db: State<Database>,
+    filter: Option<ChatFilter>,
+) -> Result<Vec<ChatWithPreview>, String> {
+    let mut filter = filter.unwrap_or_default();
+    filter.tag_ids.sort_unstable();
+    filter.tag_ids.dedup();
+    let tag_ids = serde_json::to_string(&filter.tag_ids).map_err(|e| e.to_string())?;
+
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let mut tags = load_tags_by_chat(&conn)?;
 
     let mut stmt = conn
         .prepare(
-            "SELECT c.id, c.title, c.created_at, c.updated_at, m.content, m.role
+            "SELECT c.id, c.title, c.folder_id, c.pinned, c.archived, c.created_at, c.updated_at, m.content, m.role,
+                    (SELECT COUNT(*) FROM messages m3 WHERE m3.chat_id = c.id AND m3.deleted_at IS NULL)
              FROM chats c
              LEFT JOIN messages m ON m.id = (
                  SELECT m2.id FROM messages m2
-                 WHERE m2.chat_id = c.id
+                 WHERE m2.chat_id = c.id AND m2.deleted_at IS NULL
                  ORDER BY m2.id DESC
                  LIMIT 1
              )
-             ORDER BY c.updated_at DESC",
+             WHERE c.deleted_at IS NULL
+               AND c.archived = ?1
+               AND (?2 IS NULL OR c.folder_id = ?2)
+               AND (?3 = 0 OR c.pinned = 1)
+               AND (
+                   SELECT COUNT(*) FROM chat_tags ct
+                   WHERE ct.chat_id = c.id AND ct.tag_id IN (SELECT value FROM json_each(?4))
+               ) = json_array_length(?4)
+             ORDER BY c.pinned DESC, c.updated_at DESC",
         )
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
-        .query_map([], |row| {
-            let content: Option<String> = row.get(4)?;
-            let truncated = content.map(|c| {
-                match c.char_indices().nth(100) {
-                    Some((idx, _)) => {
-                        let mut t = c[..idx].to_string();
-                        t.push('…');
-                        t
+        .query_map(
+            rusqlite::params![filter.archived, filter.folder_id, filter.pinned_only, &tag_ids],
+            |row| {
+                let content: Option<String> = row.get(7)?;
+                let truncated = content.map(|c| {
+                    match c.char_indices().nth(100) {
+                        Some((idx, _)) => {
+                            let mut t = c[..idx].to_string();
+                            t.push('…');
+                            t
+                        }
+                        None => c,
                     }
-                    None => c,
-                }
-            });
-
-            Ok(ChatWithPreview {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-                last_message: truncated,
-                last_message_role: row.get(5)?,
-            })
-        })
+                });
+
+                let id: i64 = row.get(0)?;
+                Ok(ChatWithPreview {
+                    id,
+                    title: row.get(1)?,
+                    folder_id: row.get(2)?,
+                    pinned: row.get(3)?,
+                    archived: row.get(4)?,
+                    tags: tags.remove(&id).unwrap_or_default(),
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    last_message: truncated,
+                    last_message_role: row.get(8)?,
+                    message_count: row.get(9)?,
+                })
+            },
+        )
         .map_err(|e| e.to_string())?;
 
     let mut chats = Vec::new();
@@ -75,31 +244,279 @@ pub fn list_chats(db: State<Database>) -> Result<Vec<ChatWithPreview>, String> {
     Ok(chats)
 }
 
+fn load_chat(conn: &Connection, chat_id: i64) -> Result<Chat, String> {
+    let mut chat = conn
+        .query_row(
+            "SELECT id, title, folder_id, pinned, archived, created_at, updated_at FROM chats WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![chat_id],
+            |row| {
+                Ok(Chat {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    folder_id: row.get(2)?,
+                    pinned: row.get(3)?,
+                    archived: row.get(4)?,
+                    tags: Vec::new(),
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                    settings: None,
+                })
+            },
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Chat {} not found", chat_id),
+            e => e.to_string(),
+        })?;
+
+    chat.tags = load_chat_tags(conn, chat_id)?;
+    chat.settings = load_chat_settings(conn, chat_id)?;
+    Ok(chat)
+}
+
+/// Copies a chat with its settings, tags and the messages up to and
+/// including `up_to_message_id` (all of them when `None`).
+fn copy_chat(
+    conn: &mut Connection,
+    chat_id: i64,
+    up_to_message_id: Option<i64>,
+    title: &str,
+) -> Result<Chat, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let timestamp = models::now();
+
+    let inserted = tx
+        .execute(
+            "INSERT INTO chats (title, folder_id, created_at, updated_at)
+             SELECT ?1, folder_id, ?2, ?2 FROM chats WHERE id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![title, &timestamp, chat_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if inserted == 0 {
+        return Err(format!("Chat {} not found", chat_id));
+    }
+    let new_id = tx.last_insert_rowid();
+
+    // Messages are copied one by one so their attachments can follow them
+    let message_ids: Vec<i64> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id FROM messages
+                 WHERE chat_id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR id <= ?2)
+                 ORDER BY id ASC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params![chat_id, up_to_message_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+
+        let mut ids = Vec::new();
+        for row in rows {
+            ids.push(row.map_err(|e| e.to_string())?);
+        }
+        ids
+    };
+
+    for message_id in message_ids {
+        tx.execute(
+            "INSERT INTO messages (chat_id, role, content, reasoning, created_at)
+             SELECT ?1, role, content, reasoning, created_at FROM messages WHERE id = ?2",
+            rusqlite::params![new_id, message_id],
+        )
+        .map_err(|e| e.to_string())?;
+        let new_message_id = tx.last_insert_rowid();
+
+        tx.execute(
+            "INSERT INTO attachments (message_id, file_name, mime_type, size_bytes, data, created_at)
+             SELECT ?1, file_name, mime_type, size_bytes, data, created_at
+             FROM attachments
+             WHERE message_id = ?2
+             ORDER BY id ASC",
+            rusqlite::params![new_message_id, message_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.execute(
+        "INSERT INTO chat_settings
+         (chat_id, profile_id, model, temperature, top_p, max_tokens, generation_params, preset_id, handle_x, handle_y, anchor_weights,
+          system_prompt, system_messages, updated_at)
+         SELECT ?1, profile_id, model, temperature, top_p, max_tokens, generation_params, preset_id, handle_x, handle_y, anchor_weights,
+                system_prompt, system_messages, ?2
+         FROM chat_settings
+         WHERE chat_id = ?3",
+        rusqlite::params![new_id, &timestamp, chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO chat_tags (chat_id, tag_id) SELECT ?1, tag_id FROM chat_tags WHERE chat_id = ?2",
+        rusqlite::params![new_id, chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let chat = load_chat(&tx, new_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(chat)
+}
+
 #[tauri::command]
 pub fn get_chat(db: State<Database>, chat_id: i64) -> Result<Chat, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_chat(&conn, chat_id)
+}
 
-    conn.query_row(
-        "SELECT id, title, created_at, updated_at FROM chats WHERE id = ?1",
-        rusqlite::params![chat_id],
-        |row| {
-            Ok(Chat {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-            })
-        },
+/// Starts a new chat from the history of `chat_id` up to and including
+/// `up_to_message_id`, leaving the original untouched.
+#[tauri::command]
+pub fn fork_chat(
+    db: State<Database>,
+    chat_id: i64,
+    up_to_message_id: i64,
+    new_title: Option<String>,
+) -> Result<Chat, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let (message_chat, title): (i64, String) = conn
+        .query_row(
+            "SELECT m.chat_id, c.title
+             FROM messages m
+             JOIN chats c ON c.id = m.chat_id
+             WHERE m.id = ?1 AND m.deleted_at IS NULL",
+            rusqlite::params![up_to_message_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                format!("Message {} not found", up_to_message_id)
+            }
+            e => e.to_string(),
+        })?;
+
+    if message_chat != chat_id {
+        return Err(format!(
+            "Message {} does not belong to chat {}",
+            up_to_message_id, chat_id
+        ));
+    }
+
+    let title = new_title.unwrap_or_else(|| format!("{} (fork)", title));
+    copy_chat(&mut conn, chat_id, Some(up_to_message_id), &title)
+}
+
+#[tauri::command]
+pub fn duplicate_chat(
+    db: State<Database>,
+    chat_id: i64,
+    new_title: Option<String>,
+) -> Result<Chat, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let title = match new_title {
+        Some(t) => t,
+        None => {
+            let title: String = conn
+                .query_row(
+                    "SELECT title FROM chats WHERE id = ?1 AND deleted_at IS NULL",
+                    rusqlite::params![chat_id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => format!("Chat {} not found", chat_id),
+                    e => e.to_string(),
+                })?;
+            format!("{} (copy)", title)
+        }
+    };
+
+    copy_chat(&mut conn, chat_id, None, &title)
+}
+
+#[tauri::command]
+pub fn get_chat_settings(
+    db: State<Database>,
+    chat_id: i64,
+) -> Result<Option<ChatSettings>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_chat_settings(&conn, chat_id)
+}
+
+#[tauri::command]
+pub fn set_chat_settings(
+    db: State<Database>,
+    chat_id: i64,
+    settings: ChatSettings,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    save_chat_settings(&conn, chat_id, &settings)
+}
+
+/// Sets a chat's base system prompt and how system messages in its history
+/// are treated, leaving the rest of its settings alone.
+#[tauri::command]
+pub fn set_chat_system_prompt(
+    db: State<Database>,
+    chat_id: i64,
+    system_prompt: Option<String>,
+    system_messages: Option<SystemMessageHandling>,
+) -> Result<(), String> {
+    let system_prompt = system_prompt.filter(|p| !p.trim().is_empty());
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO chat_settings (chat_id, system_prompt, system_messages, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(chat_id) DO UPDATE SET
+             system_prompt = excluded.system_prompt,
+             system_messages = excluded.system_messages,
+             updated_at = excluded.updated_at",
+        rusqlite::params![
+            chat_id,
+            &system_prompt,
+            system_messages.map(|s| s.as_str()),
+            models::now(),
+        ],
     )
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_chat_mixer_state(
+    db: State<Database>,
+    chat_id: i64,
+) -> Result<Option<ChatMixerState>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    Ok(load_chat_settings(&conn, chat_id)?.map(|s| ChatMixerState {
+        preset_id: s.preset_id,
+        handle_x: s.handle_x,
+        handle_y: s.handle_y,
+        weights: s.anchor_weights.unwrap_or_default(),
+    }))
 }
 
+#[tauri::command]
+pub fn set_chat_mixer_state(
+    db: State<Database>,
+    chat_id: i64,
+    state: ChatMixerState,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    save_chat_mixer_state(&conn, chat_id, &state)
+}
+
+/// Moves a chat to the trash. It is purged after the retention period.
 #[tauri::command]
 pub fn delete_chat(db: State<Database>, chat_id: i64) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
-    conn.execute("DELETE FROM chats WHERE id = ?1", rusqlite::params![chat_id])
-        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE chats SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        rusqlite::params![models::now(), chat_id],
+    )
+    .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -121,3 +538,113 @@ pub fn update_chat_title(
 
     Ok(())
 }
+
+#[tauri::command]
+pub fn set_chat_pinned(db: State<Database>, chat_id: i64, pinned: bool) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE chats SET pinned = ?1 WHERE id = ?2",
+        rusqlite::params![pinned, chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_chat_archived(
+    db: State<Database>,
+    chat_id: i64,
+    archived: bool,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE chats SET archived = ?1 WHERE id = ?2",
+        rusqlite::params![archived, chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Moves a chat into `folder_id`, or out of any folder when it is `None`.
+#[tauri::command]
+pub fn move_chat_to_folder(
+    db: State<Database>,
+    chat_id: i64,
+    folder_id: Option<i64>,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE chats SET folder_id = ?1 WHERE id = ?2",
+        rusqlite::params![folder_id, chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixer_state_left_out_keeps_the_saved_preset_and_handle() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let now = models::now();
+        conn.execute(
+            "INSERT INTO chats (title, created_at, updated_at) VALUES ('Chat', ?1, ?1)",
+            rusqlite::params![now],
+        )
+        .unwrap();
+        let chat_id = conn.last_insert_rowid();
+        let preset_id: i64 = conn
+            .query_row("SELECT id FROM anchor_presets LIMIT 1", [], |row| row.get(0))
+            .unwrap();
+
+        let placed = ChatMixerState {
+            preset_id: Some(preset_id),
+            handle_x: Some(120.0),
+            handle_y: Some(80.0),
+            weights: [("Calm".to_string(), 0.5)].into(),
+        };
+        save_chat_mixer_state(&conn, chat_id, &placed).unwrap();
+        let weights_only = ChatMixerState {
+            weights: [("Calm".to_string(), 0.9)].into(),
+            ..Default::default()
+        };
+        save_chat_mixer_state(&conn, chat_id, &weights_only).unwrap();
+
+        let saved = load_chat_settings(&conn, chat_id).unwrap().unwrap();
+        assert_eq!(saved.preset_id, Some(preset_id));
+        assert_eq!((saved.handle_x, saved.handle_y), (Some(120.0), Some(80.0)));
+        assert_eq!(saved.anchor_weights.unwrap()["Calm"], 0.9);
+    }
+
+    #[test]
+    fn out_of_range_weights_are_not_saved() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        conn.execute(
+            "INSERT INTO chats (title, created_at, updated_at) VALUES ('Chat', ?1, ?1)",
+            rusqlite::params![models::now()],
+        )
+        .unwrap();
+        let chat_id = conn.last_insert_rowid();
+
+        assert!(check_weights([-1.0, 0.0, 1.0]).is_ok());
+        assert!(check_weights([1.5]).is_err());
+        assert!(check_weights([f64::NAN]).is_err());
+
+        let settings = ChatSettings {
+            anchor_weights: Some([("Calm".to_string(), -2.0)].into()),
+            ..Default::default()
+        };
+        assert!(save_chat_settings(&conn, chat_id, &settings).is_err());
+        assert!(load_chat_settings(&conn, chat_id).unwrap().is_none());
+    }
+}
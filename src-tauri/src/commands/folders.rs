@@ -0,0 +1,153 @@
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::{self, Folder};
+
+/// Folders nest one level deep: a parent must itself be top-level, and a
+/// folder that has subfolders cannot be moved under another one.
+fn validate_parent(
+    conn: &Connection,
+    folder_id: Option<i64>,
+    parent_id: Option<i64>,
+) -> Result<(), String> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+
+    if folder_id == Some(parent_id) {
+        return Err("A folder cannot be its own parent".to_string());
+    }
+
+    let grandparent: Option<i64> = conn
+        .query_row(
+            "SELECT parent_id FROM folders WHERE id = ?1",
+            rusqlite::params![parent_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Folder {} not found", parent_id),
+            e => e.to_string(),
+        })?;
+
+    if grandparent.is_some() {
+        return Err("Folders can only be nested one level deep".to_string());
+    }
+
+    if let Some(id) = folder_id {
+        let has_children: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM folders WHERE parent_id = ?1)",
+                rusqlite::params![id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        if has_children {
+            return Err("A folder with subfolders cannot be nested".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_folders(db: State<Database>) -> Result<Vec<Folder>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, parent_id, created_at, updated_at
+             FROM folders
+             ORDER BY name ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Folder {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut folders = Vec::new();
+    for row in rows {
+        folders.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(folders)
+}
+
+#[tauri::command]
+pub fn create_folder(
+    db: State<Database>,
+    name: String,
+    parent_id: Option<i64>,
+) -> Result<Folder, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    validate_parent(&conn, None, parent_id)?;
+    let timestamp = models::now();
+
+    conn.execute(
+        "INSERT INTO folders (name, parent_id, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![&name, parent_id, &timestamp, &timestamp],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(Folder {
+        id: conn.last_insert_rowid(),
+        name,
+        parent_id,
+        created_at: timestamp.clone(),
+        updated_at: timestamp,
+    })
+}
+
+#[tauri::command]
+pub fn update_folder(
+    db: State<Database>,
+    folder_id: i64,
+    name: String,
+    parent_id: Option<i64>,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    validate_parent(&conn, Some(folder_id), parent_id)?;
+
+    conn.execute(
+        "UPDATE folders SET name = ?1, parent_id = ?2, updated_at = ?3 WHERE id = ?4",
+        rusqlite::params![&name, parent_id, models::now(), folder_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Deletes a folder and its subfolders. Chats inside are kept and moved out
+/// of any folder.
+#[tauri::command]
+pub fn delete_folder(db: State<Database>, folder_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM folders WHERE id = ?1",
+        rusqlite::params![folder_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -1,21 +1,90 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex as TokioMutex;
 
+use crate::commands::attachments::{self, load_attachment_data, load_attachments_by_message};
+use crate::commands::chat::{
+    check_weights, load_chat_settings, save_chat_mixer_state, save_chat_settings,
+};
+use crate::commands::messages::{append_message_reasoning, replace_message_content};
+use crate::commands::profiles::resolve_profile;
+use crate::commands::usage::{
+    self, LlmCallRecord, CALL_KIND_CONTINUATION, CALL_KIND_MIX, CALL_KIND_PRESET,
+    CALL_KIND_SUMMARY, CALL_KIND_TITLE, CALL_STATUS_CANCELLED, CALL_STATUS_ERROR, CALL_STATUS_OK,
+};
+use crate::context::{
+    self, count_tokens, HistoryStrategy, MESSAGE_OVERHEAD_TOKENS, REPLY_PRIMING_TOKENS,
+    SUMMARY_MAX_TOKENS,
+};
 use crate::db::Database;
-use crate::models::Message;
+use crate::commands::presets::{
+    kind_from_column, lay_out_in_circle, list_from_column, load_preset_defaults, save_new_preset,
+    unique_preset_name,
+};
+use crate::commands::settings::load_setting;
+use crate::models::{
+    self, AnchorKind, AnchorPreset, ChatMixerState, ChatSettings, ExamplePair, GenerationParams,
+    Message, MixStrategy, NewPresetAnchor, PresetDefaults, PresetWriteError,
+    SystemMessageHandling,
+};
+use crate::weights::DEFAULT_INFLUENCE_RADIUS;
+use crate::reasoning::{self, FilteredChunk, ReasoningFilter, DEFAULT_REASONING_FIELDS};
 
 // ---------------------------------------------------------------------------
 // Types
 // ---------------------------------------------------------------------------
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Polarity {
+    /// Steer the output toward the anchor.
+    #[default]
+    Toward,
+    /// Steer the output away from the anchor.
+    Avoid,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct WeightedAnchorInput {
     pub label: String,
     pub prompt: String,
+    /// Between -1 and 1. A negative weight avoids the anchor like `Avoid` does.
     pub weight: f64,
+    #[serde(default)]
+    pub polarity: Polarity,
+    #[serde(default)]
+    pub kind: AnchorKind,
+    #[serde(default)]
+    pub examples: Vec<ExamplePair>,
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+impl WeightedAnchorInput {
+    /// Weight with avoidance as the sign, as stored in a chat's mixer state.
+    fn signed_weight(&self) -> f64 {
+        match self.polarity {
+            Polarity::Toward => self.weight,
+            Polarity::Avoid => -self.weight.abs(),
+        }
+    }
+
+    fn avoids(&self) -> bool {
+        self.signed_weight() < 0.0
+    }
+
+    fn is_active(&self) -> bool {
+        self.weight != 0.0
+    }
+
+    /// Active and blended through the mix rather than applied directly.
+    fn is_mixed(&self) -> bool {
+        self.is_active() && self.kind == AnchorKind::Persona
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -28,12 +97,129 @@ struct StatusPayload {
     status: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ChatTitlePayload {
+    chat_id: i64,
+    title: String,
+}
+
+/// Reply expected from `GENERATE_PRESET_PROMPT`.
+#[derive(Debug, Deserialize)]
+struct GeneratedPreset {
+    #[serde(default)]
+    name: String,
+    anchors: Vec<GeneratedAnchor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedAnchor {
+    label: String,
+    prompt: String,
+    color: String,
+    icon: String,
+}
+
+fn default_chunk_tokens() -> i64 {
+    2
+}
+
+/// How fast the midtoken loop advances.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PacingConfig {
+    /// Tokens requested per continuation step.
+    #[serde(default = "default_chunk_tokens")]
+    pub chunk_tokens: i64,
+    /// Fixed sleep between steps.
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    /// Target output rate; takes precedence over `delay_ms`.
+    #[serde(default)]
+    pub tokens_per_second: Option<f64>,
+    /// Extra sleep for the first steps after a remix, so the change is visible.
+    #[serde(default)]
+    pub remix_slowdown_ms: u64,
+    #[serde(default)]
+    pub remix_slowdown_steps: u32,
+}
+
+impl PacingConfig {
+    fn from_speed(speed: &str) -> Result<Self, String> {
+        let delay_ms = match speed {
+            "slow" => 500,
+            "fast" => 0,
+            _ => return Err(format!("Unknown speed: {}. Use 'slow' or 'fast'.", speed)),
+        };
+
+        Ok(PacingConfig {
+            chunk_tokens: default_chunk_tokens(),
+            delay_ms: Some(delay_ms),
+            tokens_per_second: None,
+            remix_slowdown_ms: 0,
+            remix_slowdown_steps: 0,
+        })
+    }
+
+    /// The session's pacing: `pacing` as given, or the `speed` preset with
+    /// the active preset's chunk size. Either one is required.
+    fn resolve(
+        pacing: Option<PacingConfig>,
+        speed: Option<&str>,
+        chunk_tokens: Option<i64>,
+    ) -> Result<Self, String> {
+        let pacing = match (pacing, speed) {
+            (Some(p), _) => p,
+            (None, None) => return Err("Pass either a speed or a pacing".to_string()),
+            (None, Some(speed)) => {
+                let mut p = PacingConfig::from_speed(speed)?;
+                if let Some(chunk_tokens) = chunk_tokens {
+                    p.chunk_tokens = chunk_tokens;
+                }
+                p
+            }
+        };
+        pacing.validate()?;
+        Ok(pacing)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(1..=256).contains(&self.chunk_tokens) {
+            return Err("Chunk size must be between 1 and 256 tokens".to_string());
+        }
+        if let Some(rate) = self.tokens_per_second {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err("Tokens per second must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn step_delay_ms(&self, call_latency_ms: i64) -> u64 {
+        match self.tokens_per_second {
+            // The request itself already took a while, only sleep for the rest
+            Some(rate) => ((self.chunk_tokens as f64 / rate * 1000.0) as u64)
+                .saturating_sub(call_latency_ms.max(0) as u64),
+            None => self.delay_ms.unwrap_or(0),
+        }
+    }
+}
+
 pub struct MixingSession {
+    pub session_id: String,
+    pub chat_id: i64,
     pub is_active: bool,
     pub is_paused: bool,
     pub is_mixing: bool,
-    pub midtoken_sleep_ms: u64,
+    pub pacing: PacingConfig,
+    pub remix_slowdown_left: u32,
+    pub generation: GenerationParams,
+    /// The active preset's meta-prompt template and mix strategy, for remixes.
+    pub meta_prompt: Option<String>,
+    pub mix_strategy: MixStrategy,
     pub current_promx: String,
+    /// Constraint and vocabulary text appended after the mixed prompt.
+    pub directives: String,
+    /// Few-shot example messages sent ahead of the history.
+    pub examples: Vec<serde_json::Value>,
     pub generated_text: String,
     pub cancel_token: tokio::sync::watch::Sender<bool>,
 }
@@ -46,6 +232,55 @@ impl Default for SessionState {
     }
 }
 
+struct LlmReply {
+    content: String,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+    latency_ms: i64,
+    finish_reason: Option<String>,
+    message: serde_json::Value,
+}
+
+impl LlmReply {
+    /// Reasoning the provider returned next to `content`, e.g. `reasoning_content`.
+    fn reasoning(&self, field: Option<&str>) -> Option<&str> {
+        match field {
+            Some(f) => self.message[f].as_str(),
+            None => DEFAULT_REASONING_FIELDS
+                .iter()
+                .find_map(|f| self.message[*f].as_str()),
+        }
+        .filter(|r| !r.is_empty())
+    }
+}
+
+/// A failed provider call, with whatever usage the provider reported.
+struct LlmCallError {
+    message: String,
+    prompt_tokens: Option<i64>,
+    completion_tokens: Option<i64>,
+    latency_ms: i64,
+}
+
+impl std::fmt::Display for LlmCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<LlmCallError> for String {
+    fn from(e: LlmCallError) -> String {
+        e.message
+    }
+}
+
+/// Chat and session a provider call is accounted to.
+#[derive(Debug, Clone)]
+struct CallScope {
+    chat_id: Option<i64>,
+    session_id: Option<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -54,9 +289,10 @@ const META_PROMPT: &str = r#"You are PromptMixer-Bot.
 
 Goal
 • Create ONE brand-new prompt ("COMPOSITE_PROMPT") that blends the intent, tone and key instructions of the N source prompts listed below.
-• Each source prompt has a WEIGHT between 0.0 and 1.0.
+• Each source prompt has a WEIGHT between -1.0 and 1.0.
   - 0.0  → no influence
   - 1.0  → maximal influence
+  - -1.0 → maximal avoidance: the COMPOSITE_PROMPT must steer AWAY from that source's goal, tone and style
 • Do NOT carry over any text verbatim from the sources. Produce original wording only.
 
 Procedure (follow EXACTLY):
@@ -67,14 +303,16 @@ b) tone/style descriptors (≤ 5 words)
 c) critical instructions (≤ 40 words)
 
 STEP 2 - Apply Weights
-• Multiply the importance of every item from STEP 1 by its weight.
+• Multiply the importance of every item from STEP 1 by the absolute value of its weight.
 • Discard any item whose weighted importance < 0.10 (threshold).
+• Items from sources with a negative weight become things to avoid.
 
 STEP 3 - Draft
 • Write the COMPOSITE_PROMPT in 2-nd person ("You ...").
 • Length 120-180 words.
 • Integrate the weighted items so their relative emphasis matches their numeric weights.
 • Maintain coherence: flow, consistent voice, no contradictions.
+• State avoided items as explicit instructions ("Do not ...", "Never ..."), firmer for stronger negative weights.
 
 STEP 4 - Self-Check
 • Verify that no sentence is copied from sources.
@@ -96,23 +334,204 @@ Important Rules
 
 Begin."#;
 
+const SUMMARY_PROMPT: &str = r#"Summarize the conversation below so that it can be continued without the original messages.
+Keep names, facts, decisions, open questions and the overall tone. Write plain prose in at most 200 words.
+Reply with the summary and nothing else.
+
+Conversation
+```
+{transcript}
+```"#;
+
+/// Max tokens for a composite prompt produced by the meta prompt.
+const MIX_MAX_TOKENS: i64 = 512;
+
+const TITLE_PROMPT: &str = r#"Write a short title of at most six words for the conversation below.
+Reply with the title only, without quotes and without a trailing period.
+
+Conversation
+```
+{transcript}
+```"#;
+
+const TITLE_MAX_TOKENS: i64 = 32;
+
+/// Fallback title length: the first user message cut at a word boundary.
+const TITLE_FALLBACK_CHARS: usize = 50;
+
+/// How much of each message goes into the title prompt.
+const TITLE_EXCERPT_CHARS: usize = 1000;
+
+const GENERATE_PRESET_PROMPT: &str = r##"Design a set of {count} anchors for a prompt mixer themed around: {description}
+
+Each anchor is a distinct persona or style that a writer can blend with the others.
+Give every anchor:
+- "label": a short name of one to three words, different from all other labels
+- "prompt": second-person instructions ("You are ...") of two to four sentences describing its voice, attitude and style
+- "color": a hex color like "#A0C4FF" that suits it, distinct from the other colors
+- "icon": one to three ASCII characters that evoke it, without spaces
+
+Reply with JSON only, in this shape:
+{"name": "<short preset name>", "anchors": [{"label": "...", "prompt": "...", "color": "#RRGGBB", "icon": "..."}]}"##;
+
+/// Completion budget per requested anchor in `generate_preset`.
+const GENERATE_PRESET_TOKENS_PER_ANCHOR: i64 = 300;
+
+/// Bounds on the number of anchors `generate_preset` asks for.
+const GENERATE_PRESET_MIN_ANCHORS: usize = 2;
+const GENERATE_PRESET_MAX_ANCHORS: usize = 12;
+
+/// Longest icon, in characters, a generated anchor may have.
+const GENERATED_ICON_MAX_CHARS: usize = 3;
+
+// Global model parameter defaults, used when neither the call nor the chat sets them
+const DEFAULT_TEMPERATURE: f64 = 0.7;
+const DEFAULT_MAX_TOKENS: i64 = 2048;
+const DEFAULT_TOP_P: f64 = 1.0;
+
 const EXTRA_INSTRUCTIONS: &str = r#"
 Avoid overly pretentious language - you're not great at it and it comes off as stupid.
 You must roleplay the emotions and tones described above.
 Reflect these emotions in every word you say.
 When you see unfinished sentence - you continue it exactly where it was left, even if it was halfway through a word."#;
 
+/// Builds the leading system message: the chat's base instructions first,
+/// then the mixed prompt, then `EXTRA_INSTRUCTIONS`, then the anchor
+/// directives. Steering colors the base instructions rather than replacing
+/// them.
+fn compose_system_prompt(base: &str, mixed: &str, directives: &str) -> String {
+    let mut prompt = if base.is_empty() {
+        format!("{}{}", mixed, EXTRA_INSTRUCTIONS)
+    } else {
+        format!("{}\n\n{}{}", base, mixed, EXTRA_INSTRUCTIONS)
+    };
+    if !directives.is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(directives);
+    }
+    prompt
+}
+
+/// How many of an anchor's `len` examples or items its weight applies: none
+/// at 0, all at full weight.
+fn applied_count(weight: f64, len: usize) -> usize {
+    ((weight.abs().min(1.0) * len as f64).ceil() as usize).min(len)
+}
+
+/// Constraint and vocabulary anchors as text for the system prompt. They
+/// bypass the mix, so their entries reach the model verbatim.
+fn anchor_directives(anchors: &[WeightedAnchorInput]) -> String {
+    let mut constraints = Vec::new();
+    let mut favoured = Vec::new();
+    let mut avoided = Vec::new();
+
+    for anchor in anchors.iter().filter(|a| a.is_active()) {
+        let entries: Vec<&str> = anchor
+            .items
+            .iter()
+            .map(|i| i.trim())
+            .filter(|i| !i.is_empty())
+            .collect();
+        let applied = &entries[..applied_count(anchor.weight, entries.len())];
+
+        match anchor.kind {
+            // There is no opposite of a rule, so avoided constraints are dropped
+            AnchorKind::Constraint if !anchor.avoids() => constraints.extend_from_slice(applied),
+            AnchorKind::Vocabulary if anchor.avoids() => avoided.extend_from_slice(applied),
+            AnchorKind::Vocabulary => favoured.extend_from_slice(applied),
+            _ => {}
+        }
+    }
+
+    let mut sections = Vec::new();
+    if !constraints.is_empty() {
+        let rules: Vec<String> = constraints.iter().map(|c| format!("- {}", c)).collect();
+        sections.push(format!("Always follow these rules:\n{}", rules.join("\n")));
+    }
+    if !favoured.is_empty() {
+        sections.push(format!("Use these words where they fit: {}", favoured.join(", ")));
+    }
+    if !avoided.is_empty() {
+        sections.push(format!("Never use these words: {}", avoided.join(", ")));
+    }
+    sections.join("\n\n")
+}
+
+/// Tokens the directives and examples take with every anchor at full weight,
+/// so raising a weight mid-session cannot overflow the context window.
+fn anchor_content_tokens(model: &str, anchors: &[WeightedAnchorInput]) -> usize {
+    let full: Vec<WeightedAnchorInput> = anchors
+        .iter()
+        .map(|a| WeightedAnchorInput {
+            weight: 1.0,
+            polarity: Polarity::Toward,
+            ..a.clone()
+        })
+        .collect();
+    let examples = few_shot_messages(&full);
+    count_tokens(model, &anchor_directives(&full))
+        + examples
+            .iter()
+            .map(|m| count_tokens(model, m["content"].as_str().unwrap_or_default()))
+            .sum::<usize>()
+        + examples.len() * MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Example exchanges of few-shot anchors as user/assistant message pairs.
+fn few_shot_messages(anchors: &[WeightedAnchorInput]) -> Vec<serde_json::Value> {
+    anchors
+        .iter()
+        .filter(|a| a.kind == AnchorKind::FewShot && a.is_active() && !a.avoids())
+        .flat_map(|a| a.examples.iter().take(applied_count(a.weight, a.examples.len())))
+        .flat_map(|e| {
+            [
+                serde_json::json!({ "role": "user", "content": e.user }),
+                serde_json::json!({ "role": "assistant", "content": e.assistant }),
+            ]
+        })
+        .collect()
+}
+
+/// Applies a chat's system message handling to its history. Returns the
+/// base instructions for `compose_system_prompt` and the history to send.
+fn split_system_messages(
+    system_prompt: Option<&str>,
+    history: Vec<Message>,
+    handling: SystemMessageHandling,
+) -> (String, Vec<Message>) {
+    let mut base: Vec<String> = system_prompt
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| vec![p.to_string()])
+        .unwrap_or_default();
+
+    if handling == SystemMessageHandling::Keep {
+        return (base.join("\n\n"), history);
+    }
+
+    let (system, rest): (Vec<Message>, Vec<Message>) =
+        history.into_iter().partition(|m| m.role == "system");
+
+    if handling == SystemMessageHandling::Merge {
+        base.extend(system.into_iter().map(|m| m.content));
+    }
+
+    (base.join("\n\n"), rest)
+}
+
 fn format_sources(anchors: &[WeightedAnchorInput]) -> String {
     anchors
         .iter()
-        .filter(|a| a.weight > 0.0)
+        .filter(|a| a.is_mixed())
         .enumerate()
         .map(|(i, a)| {
+            let avoid = if a.avoids() { ", AVOID" } else { "" };
             format!(
-                "Source {} — \"{}\" (weight {:.2}):\n{}",
+                "Source {} — \"{}\" (weight {:.2}{}):\n{}",
                 i + 1,
                 a.label,
-                a.weight,
+                a.signed_weight(),
+                avoid,
                 a.prompt
             )
         })
@@ -120,49 +539,82 @@ fn format_sources(anchors: &[WeightedAnchorInput]) -> String {
         .join("\n\n")
 }
 
-fn strip_think_tags(text: &str, in_think: &mut bool) -> String {
-    let mut result = String::new();
-    let mut remaining = text;
-    let was_in_think = *in_think;
+/// Fills a meta-prompt template (the built-in one by default) with the
+/// formatted sources.
+fn build_mix_prompt(anchors: &[WeightedAnchorInput], template: Option<&str>) -> String {
+    let sources_text = format_sources(anchors);
+    template
+        .unwrap_or(META_PROMPT)
+        .replace("{sources}", &sources_text)
+}
 
-    while !remaining.is_empty() {
-        if *in_think {
-            if let Some(end_pos) = remaining.find("</think>") {
-                *in_think = false;
-                remaining = &remaining[end_pos + 8..]; // skip </think>
-            } else {
-                break; // still inside think block, suppress all
-            }
-        } else {
-            if let Some(start_pos) = remaining.find("<think>") {
-                result.push_str(&remaining[..start_pos]);
-                *in_think = true;
-                remaining = &remaining[start_pos + 7..]; // skip <think>
-            } else {
-                result.push_str(remaining);
-                break;
-            }
-        }
+/// Mixes without the LLM where `strategy` allows it: under `Auto` when at
+/// most one persona anchor steers toward something, under `Local` always.
+/// The result is the positive prompts, followed by avoidance instructions for
+/// the avoided anchors. Returns `None` when the meta prompt is needed.
+fn local_mix(
+    anchors: &[WeightedAnchorInput],
+    strategy: MixStrategy,
+) -> Result<Option<String>, String> {
+    if !anchors.iter().any(|a| a.is_active()) {
+        return Err("No active anchors (all weights are 0)".to_string());
     }
 
-    // Only trim leading whitespace when transitioning out of a think block,
-    // not on every chunk (which would eat spaces between words).
-    if was_in_think {
-        result.trim_start().to_string()
-    } else {
-        result
+    let (avoided, toward): (Vec<&WeightedAnchorInput>, Vec<&WeightedAnchorInput>) =
+        anchors.iter().filter(|a| a.is_mixed()).partition(|a| a.avoids());
+
+    // Only directly applied kinds are active, so there is nothing to mix
+    if avoided.is_empty() && toward.is_empty() {
+        return Ok(Some(String::new()));
     }
-}
 
-fn build_mix_prompt(anchors: &[WeightedAnchorInput]) -> String {
-    let active: Vec<&WeightedAnchorInput> = anchors.iter().filter(|a| a.weight > 0.0).collect();
+    let needs_llm = match strategy {
+        MixStrategy::Auto => toward.len() > 1,
+        MixStrategy::Llm => true,
+        MixStrategy::Local => false,
+    };
+    if needs_llm {
+        return Ok(None);
+    }
 
-    if active.len() == 1 {
-        return active[0].prompt.clone();
+    let mut prompt = match toward.as_slice() {
+        [] => String::new(),
+        [only] => only.prompt.clone(),
+        several => {
+            let styles = several
+                .iter()
+                .map(|a| format!("- \"{}\" (strength {:.2}): {}", a.label, a.weight, a.prompt))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "Combine the following styles, leaning on each according to its strength:\n{}",
+                styles
+            )
+        }
+    };
+    if !avoided.is_empty() {
+        let rules = avoided
+            .iter()
+            .map(|a| {
+                format!(
+                    "- \"{}\" (strength {:.2}): do not write like this: {}",
+                    a.label,
+                    a.weight.abs(),
+                    a.prompt
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !prompt.is_empty() {
+            prompt.push_str("\n\n");
+        }
+        prompt.push_str(
+            "Steer clearly away from the following styles, more firmly the higher their strength:\n",
+        );
+        prompt.push_str(&rules);
     }
 
-    let sources_text = format_sources(anchors);
-    META_PROMPT.replace("{sources}", &sources_text)
+    Ok(Some(prompt))
 }
 
 fn new_llm_client() -> Result<reqwest::Client, String> {
@@ -172,26 +624,82 @@ fn new_llm_client() -> Result<reqwest::Client, String> {
         .map_err(|e| format!("Failed to create HTTP client: {}", e))
 }
 
-async fn call_llm(
+fn apply_generation_params(body: &mut serde_json::Value, params: &GenerationParams) {
+    if let Some(ref stop) = params.stop {
+        body["stop"] = serde_json::json!(stop);
+    }
+    if let Some(seed) = params.seed {
+        body["seed"] = serde_json::json!(seed);
+    }
+    if let Some(penalty) = params.presence_penalty {
+        body["presence_penalty"] = serde_json::json!(penalty);
+    }
+    if let Some(penalty) = params.frequency_penalty {
+        body["frequency_penalty"] = serde_json::json!(penalty);
+    }
+    if let Some(ref bias) = params.logit_bias {
+        body["logit_bias"] = serde_json::json!(bias);
+    }
+    if let Some(ref extra) = params.extra_body {
+        for (key, value) in extra {
+            // The model and conversation always come from the session
+            if matches!(key.as_str(), "model" | "messages" | "stream") {
+                continue;
+            }
+            body[key.as_str()] = value.clone();
+        }
+    }
+}
+
+/// Parameters for the meta prompt calls: only the seed carries over, so that
+/// a seeded session also gets reproducible composite prompts.
+fn mix_generation_params(generation: &GenerationParams) -> GenerationParams {
+    GenerationParams {
+        seed: generation.seed,
+        ..Default::default()
+    }
+}
+
+/// A chat completion request. Messages are serialized straight from the
+/// caller's slice, so borrowed history (attachments included) isn't copied.
+#[derive(Serialize)]
+struct CompletionRequest<'a, M> {
+    messages: &'a [M],
+    #[serde(flatten)]
+    params: serde_json::Value,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn call_llm<M: Serialize>(
     client: &reqwest::Client,
     provider_url: &str,
     api_key: &str,
     model: &str,
-    messages: &[serde_json::Value],
+    messages: &[M],
     max_tokens: i64,
     temperature: f64,
     top_p: f64,
-) -> Result<String, String> {
+    generation: &GenerationParams,
+) -> Result<LlmReply, LlmCallError> {
     let url = format!("{}/chat/completions", provider_url.trim_end_matches('/'));
 
-    let body = serde_json::json!({
+    let mut params = serde_json::json!({
         "model": model,
-        "messages": messages,
         "max_tokens": max_tokens,
         "temperature": temperature,
         "top_p": top_p,
         "stream": false,
     });
+    apply_generation_params(&mut params, generation);
+    let body = CompletionRequest { messages, params };
+
+    let started = std::time::Instant::now();
+    let fail = |message: String, usage: &serde_json::Value| LlmCallError {
+        message,
+        prompt_tokens: usage["prompt_tokens"].as_i64(),
+        completion_tokens: usage["completion_tokens"].as_i64(),
+        latency_ms: started.elapsed().as_millis() as i64,
+    };
 
     let response = client
         .post(&url)
@@ -200,26 +708,177 @@ async fn call_llm(
         .json(&body)
         .send()
         .await
-        .map_err(|e| format!("HTTP request failed: {}", e))?;
+        .map_err(|e| fail(format!("HTTP request failed: {}", e), &serde_json::Value::Null))?;
 
     let status = response.status();
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| fail(format!("Failed to read response: {}", e), &serde_json::Value::Null))?;
 
     if !status.is_success() {
-        return Err(format!("LLM API error ({}): {}", status, response_text));
+        // Some providers still report usage on an error body
+        let usage = serde_json::from_str::<serde_json::Value>(&response_text)
+            .map(|json| json["usage"].clone())
+            .unwrap_or_default();
+        return Err(fail(
+            format!("LLM API error ({}): {}", status, response_text),
+            &usage,
+        ));
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&response_text)
+        .map_err(|e| fail(format!("Invalid JSON: {}", e), &serde_json::Value::Null))?;
+
+    let choice = json["choices"].get(0);
+    let message = choice
+        .map(|c| c["message"].clone())
+        .unwrap_or(serde_json::Value::Null);
+    let finish_reason = choice
+        .and_then(|c| c["finish_reason"].as_str())
+        .map(|r| r.to_string());
+
+    // Reasoning models may spend a whole chunk on `reasoning_content` and
+    // return an empty or null `content`
+    let content = match message["content"].as_str() {
+        Some(c) => c.to_string(),
+        None if DEFAULT_REASONING_FIELDS
+            .iter()
+            .any(|f| message[*f].is_string()) =>
+        {
+            String::new()
+        }
+        None => return Err(fail("No content in LLM response".to_string(), &json["usage"])),
+    };
+
+    Ok(LlmReply {
+        content,
+        prompt_tokens: json["usage"]["prompt_tokens"].as_i64(),
+        completion_tokens: json["usage"]["completion_tokens"].as_i64(),
+        latency_ms: started.elapsed().as_millis() as i64,
+        finish_reason,
+        message,
+    })
+}
+
+/// Where `text` has to be cut so no stop sequence gets through. Callers
+/// hold back a possible start of a stop (see `stop_prefix_len`) and put it
+/// in front of the next chunk, so a stop split across chunks is found whole.
+fn stop_sequence_cut(text: &str, stops: &[String]) -> Option<usize> {
+    stops
+        .iter()
+        .filter(|stop| !stop.is_empty())
+        .filter_map(|stop| text.find(stop.as_str()))
+        .min()
+}
+
+/// Length of the longest end of `text` that could be the start of a stop
+/// sequence, which must not be shown until the next chunk settles it.
+fn stop_prefix_len(text: &str, stops: &[String]) -> usize {
+    text.char_indices()
+        .map(|(i, _)| &text[i..])
+        .find(|tail| stops.iter().any(|stop| stop.len() > tail.len() && stop.starts_with(tail)))
+        .map_or(0, str::len)
+}
+
+fn emit_filtered(app: &AppHandle, filtered: FilteredChunk) {
+    // Emit only non-empty text
+    if !filtered.hidden.is_empty() {
+        let _ = app.emit(
+            "llm:reasoning",
+            TokenPayload {
+                text: filtered.hidden,
+            },
+        );
+    }
+    if !filtered.visible.is_empty() {
+        let _ = app.emit(
+            "llm:token",
+            TokenPayload {
+                text: filtered.visible,
+            },
+        );
+    }
+}
+
+fn estimate_prompt_tokens<M: Borrow<serde_json::Value>>(model: &str, messages: &[M]) -> usize {
+    messages
+        .iter()
+        .map(|m| context::count_content_tokens(model, &m.borrow()["content"]) + MESSAGE_OVERHEAD_TOKENS)
+        .sum::<usize>()
+        + REPLY_PRIMING_TOKENS
+}
+
+/// Records a call in `llm_calls`, successful or not. Token counts the
+/// provider did not report are estimated for successful calls and taken as
+/// 0 for failed ones. Accounting failures never stop generation.
+fn record_usage<M: Borrow<serde_json::Value>>(
+    db: &Database,
+    scope: &CallScope,
+    kind: &str,
+    model: &str,
+    messages: &[M],
+    result: &Result<LlmReply, LlmCallError>,
+) {
+    let record = match result {
+        Ok(reply) => LlmCallRecord {
+            chat_id: scope.chat_id,
+            session_id: scope.session_id.as_deref(),
+            kind,
+            model,
+            status: CALL_STATUS_OK,
+            prompt_tokens: reply
+                .prompt_tokens
+                .unwrap_or_else(|| estimate_prompt_tokens(model, messages) as i64),
+            completion_tokens: reply
+                .completion_tokens
+                .unwrap_or_else(|| count_tokens(model, &reply.content) as i64),
+            estimated: reply.prompt_tokens.is_none() || reply.completion_tokens.is_none(),
+            latency_ms: reply.latency_ms,
+        },
+        Err(e) => LlmCallRecord {
+            chat_id: scope.chat_id,
+            session_id: scope.session_id.as_deref(),
+            kind,
+            model,
+            status: CALL_STATUS_ERROR,
+            prompt_tokens: e.prompt_tokens.unwrap_or(0),
+            completion_tokens: e.completion_tokens.unwrap_or(0),
+            estimated: false,
+            latency_ms: e.latency_ms,
+        },
+    };
+
+    if let Err(e) = usage::record_llm_call(db, &record) {
+        eprintln!("Failed to record LLM call: {}", e);
     }
+}
 
-    let json: serde_json::Value =
-        serde_json::from_str(&response_text).map_err(|e| format!("Invalid JSON: {}", e))?;
+/// Records a call abandoned because its session was cancelled. The request
+/// already reached the provider, so its prompt is counted (estimated).
+fn record_cancelled_call<M: Borrow<serde_json::Value>>(
+    db: &Database,
+    scope: &CallScope,
+    kind: &str,
+    model: &str,
+    messages: &[M],
+    latency_ms: i64,
+) {
+    let record = LlmCallRecord {
+        chat_id: scope.chat_id,
+        session_id: scope.session_id.as_deref(),
+        kind,
+        model,
+        status: CALL_STATUS_CANCELLED,
+        prompt_tokens: estimate_prompt_tokens(model, messages) as i64,
+        completion_tokens: 0,
+        estimated: true,
+        latency_ms,
+    };
 
-    json["choices"]
-        .get(0)
-        .and_then(|c| c["message"]["content"].as_str())
-        .map(|s| s.to_string())
-        .ok_or_else(|| "No content in LLM response".to_string())
+    if let Err(e) = usage::record_llm_call(db, &record) {
+        eprintln!("Failed to record LLM call: {}", e);
+    }
 }
 
 fn load_messages_from_db(
@@ -230,9 +889,9 @@ fn load_messages_from_db(
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, chat_id, role, content, created_at
+            "SELECT id, chat_id, role, content, reasoning, created_at
              FROM messages
-             WHERE chat_id = ?1
+             WHERE chat_id = ?1 AND deleted_at IS NULL
              ORDER BY id ASC",
         )
         .map_err(|e| e.to_string())?;
@@ -244,7 +903,9 @@ fn load_messages_from_db(
                 chat_id: row.get(1)?,
                 role: row.get(2)?,
                 content: row.get(3)?,
-                created_at: row.get(4)?,
+                reasoning: row.get(4)?,
+                created_at: row.get(5)?,
+                attachments: Vec::new(),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -254,74 +915,657 @@ fn load_messages_from_db(
         messages.push(row.map_err(|e| e.to_string())?);
     }
 
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let mut attachments = load_attachments_by_message(&conn, &ids)?;
+    for message in &mut messages {
+        message.attachments = attachments.remove(&message.id).unwrap_or_default();
+    }
+
     Ok(messages)
 }
 
-fn messages_to_json(messages: &[Message]) -> Vec<serde_json::Value> {
+/// Rebuilds the weighted anchors a chat was last run with from its preset
+/// and saved weights.
+fn anchors_from_chat_settings(
+    conn: &rusqlite::Connection,
+    settings: &ChatSettings,
+) -> Result<Vec<WeightedAnchorInput>, String> {
+    let (Some(preset_id), Some(weights)) = (settings.preset_id, settings.anchor_weights.as_ref())
+    else {
+        return Err("No anchors given and the chat has no saved mixer state".to_string());
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.label, a.prompt, a.kind, a.examples, a.items
+             FROM preset_anchors a
+             JOIN anchor_presets p ON p.id = a.preset_id
+             WHERE a.preset_id = ?1 AND p.deleted_at IS NULL
+             ORDER BY a.sort_order ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![preset_id], |row| {
+            let label: String = row.get(0)?;
+            Ok(WeightedAnchorInput {
+                // Saved weights carry avoidance as their sign
+                weight: weights.get(&label).copied().unwrap_or(0.0),
+                polarity: Polarity::Toward,
+                label,
+                prompt: row.get(1)?,
+                kind: kind_from_column(row.get(2)?),
+                examples: list_from_column(row.get(3)?),
+                items: list_from_column(row.get(4)?),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut anchors = Vec::new();
+    for row in rows {
+        anchors.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(anchors)
+}
+
+/// Serializes messages for the provider. Messages with attachments become
+/// multimodal content parts; `attachment_data` holds their contents by id.
+fn messages_to_json(
+    messages: &[Message],
+    attachment_data: &HashMap<i64, Vec<u8>>,
+) -> Vec<serde_json::Value> {
     messages
         .iter()
         .map(|m| {
-            serde_json::json!({
-                "role": m.role,
-                "content": m.content,
-            })
+            if m.attachments.is_empty() {
+                serde_json::json!({
+                    "role": m.role,
+                    "content": m.content,
+                })
+            } else {
+                serde_json::json!({
+                    "role": m.role,
+                    "content": attachments::content_parts(&m.content, &m.attachments, attachment_data),
+                })
+            }
         })
         .collect()
 }
 
+async fn summarize_history(
+    db: &Database,
+    scope: &CallScope,
+    provider_url: &str,
+    api_key: &str,
+    model: &str,
+    dropped: Vec<Message>,
+    budget: usize,
+) -> Result<String, String> {
+    // The summary request itself has to fit, so only the newest dropped turns go in
+    let transcript_budget = budget.saturating_sub(count_tokens(model, SUMMARY_PROMPT));
+    let plan = context::fit_history(
+        model,
+        dropped,
+        transcript_budget,
+        &HistoryStrategy::SlidingWindow,
+    );
+
+    let transcript = plan
+        .kept
+        .iter()
+        .map(|m| format!("{}: {}", m.role, m.content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let client = new_llm_client()?;
+    let summary_messages = vec![serde_json::json!({
+        "role": "user",
+        "content": SUMMARY_PROMPT.replace("{transcript}", &transcript),
+    })];
+
+    let result = call_llm(
+        &client,
+        provider_url,
+        api_key,
+        model,
+        &summary_messages,
+        SUMMARY_MAX_TOKENS as i64,
+        0.3,
+        1.0,
+        &GenerationParams::default(),
+    )
+    .await;
+
+    record_usage(db, scope, CALL_KIND_SUMMARY, model, &summary_messages, &result);
+    Ok(result?.content)
+}
+
+/// Cuts `text` to at most `max_chars` characters, backing up to the last
+/// space if there is one.
+fn truncate_at_word_boundary(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        None => text.to_string(),
+        Some((idx, _)) => {
+            let truncated = &text[..idx];
+            match truncated.rfind(' ') {
+                Some(space) if space > 0 => truncated[..space].to_string(),
+                _ => truncated.to_string(),
+            }
+        }
+    }
+}
+
+/// Reduces a model reply to a bare title, or `None` if nothing usable is left.
+fn clean_title(reply: &str) -> Option<String> {
+    let mut filter = ReasoningFilter::new(reasoning::default_delimiters());
+    let mut visible = filter.push(reply).visible;
+    visible.push_str(&filter.finish().visible);
+
+    let line = visible.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.strip_prefix("Title:").unwrap_or(line);
+    let title = line
+        .trim()
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '*' | '#' | '`'))
+        .trim_end_matches('.')
+        .trim();
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(truncate_at_word_boundary(title, TITLE_FALLBACK_CHARS * 2))
+    }
+}
+
+/// Reads the JSON object out of a `GENERATE_PRESET_PROMPT` reply, ignoring
+/// reasoning and any code fence or prose around it.
+fn parse_generated_preset(reply: &str) -> Result<GeneratedPreset, String> {
+    let mut filter = ReasoningFilter::new(reasoning::default_delimiters());
+    let mut visible = filter.push(reply).visible;
+    visible.push_str(&filter.finish().visible);
+
+    let json = match (visible.find('{'), visible.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &visible[start..=end],
+        _ => return Err("The model did not reply with a JSON preset".to_string()),
+    };
+
+    serde_json::from_str(json).map_err(|e| format!("The model replied with an invalid preset: {}", e))
+}
+
+/// Checks the model's anchors and turns the first `count` into persona
+/// anchors laid out in a circle. Colors and the rest are checked again when
+/// saving.
+fn generated_anchors(
+    anchors: Vec<GeneratedAnchor>,
+    count: usize,
+) -> Result<Vec<NewPresetAnchor>, String> {
+    if anchors.len() < count {
+        return Err(format!(
+            "The model returned {} anchors instead of {}",
+            anchors.len(),
+            count
+        ));
+    }
+
+    let mut labels = std::collections::HashSet::new();
+    let mut anchors = anchors
+        .into_iter()
+        .take(count)
+        .enumerate()
+        .map(|(i, anchor)| {
+            let label = anchor.label.trim().to_string();
+            if !labels.insert(label.to_lowercase()) {
+                return Err(format!("The model returned \"{}\" more than once", label));
+            }
+            let icon = anchor.icon.trim().to_string();
+            let icon_chars = icon.chars().count();
+            if icon_chars == 0
+                || icon_chars > GENERATED_ICON_MAX_CHARS
+                || !icon.chars().all(|c| c.is_ascii_graphic())
+            {
+                return Err(format!(
+                    "The model returned an unusable icon for \"{}\": {:?}",
+                    label, icon
+                ));
+            }
+
+            Ok(NewPresetAnchor {
+                anchor_id: None,
+                label,
+                prompt: anchor.prompt.trim().to_string(),
+                icon_small: icon.clone(),
+                icon_large: icon,
+                color: anchor.color.trim().to_string(),
+                position_x: 0.0,
+                position_y: 0.0,
+                influence_radius: DEFAULT_INFLUENCE_RADIUS,
+                sort_order: i as i32,
+                kind: AnchorKind::Persona,
+                examples: Vec::new(),
+                items: Vec::new(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    lay_out_in_circle(&mut anchors);
+
+    Ok(anchors)
+}
+
 // ---------------------------------------------------------------------------
 // Commands
 // ---------------------------------------------------------------------------
 
+/// Options for one run of the midtoken loop.
+struct SessionRequest {
+    chat_id: i64,
+    anchors: Option<Vec<WeightedAnchorInput>>,
+    provider_url: String,
+    api_key: String,
+    model: Option<String>,
+    /// "slow" or "fast"; ignored when `pacing` is given.
+    speed: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<i64>,
+    top_p: Option<f64>,
+    profile_id: Option<i64>,
+    history_strategy: Option<HistoryStrategy>,
+    pacing: Option<PacingConfig>,
+    generation: Option<GenerationParams>,
+    /// Preset whose defaults apply; the chat's saved preset when absent.
+    preset_id: Option<i64>,
+    /// Handle position the given anchors' weights were read at, saved with them.
+    handle_x: Option<f64>,
+    handle_y: Option<f64>,
+    /// Assistant message to keep writing into instead of starting a new reply.
+    continue_message_id: Option<i64>,
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_mixing_session(
     app: AppHandle,
     db: State<'_, Database>,
     session: State<'_, SessionState>,
     chat_id: i64,
-    anchors: Vec<WeightedAnchorInput>,
+    anchors: Option<Vec<WeightedAnchorInput>>,
     provider_url: String,
     api_key: String,
-    model: String,
-    speed: String,
+    model: Option<String>,
+    speed: Option<String>,
+    temperature: Option<f64>,
+    max_tokens: Option<i64>,
+    top_p: Option<f64>,
+    profile_id: Option<i64>,
+    history_strategy: Option<HistoryStrategy>,
+    pacing: Option<PacingConfig>,
+    generation: Option<GenerationParams>,
+    preset_id: Option<i64>,
+    handle_x: Option<f64>,
+    handle_y: Option<f64>,
+) -> Result<(), String> {
+    let request = SessionRequest {
+        chat_id,
+        anchors,
+        provider_url,
+        api_key,
+        model,
+        speed,
+        temperature,
+        max_tokens,
+        top_p,
+        profile_id,
+        history_strategy,
+        pacing,
+        generation,
+        preset_id,
+        handle_x,
+        handle_y,
+        continue_message_id: None,
+    };
+    run_mixing_session(app, db, session, request).await
+}
+
+/// Keeps generating into an existing (possibly hand-edited) assistant
+/// message under the chat's current mix. When the run ends the message is
+/// updated in place, with its previous text kept as a revision.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn continue_message(
+    app: AppHandle,
+    db: State<'_, Database>,
+    session: State<'_, SessionState>,
+    message_id: i64,
+    anchors: Option<Vec<WeightedAnchorInput>>,
+    provider_url: String,
+    api_key: String,
+    speed: Option<String>,
+    pacing: Option<PacingConfig>,
+    handle_x: Option<f64>,
+    handle_y: Option<f64>,
+) -> Result<(), String> {
+    let chat_id: i64 = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT chat_id FROM messages WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![message_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Message {} not found", message_id),
+            e => e.to_string(),
+        })?
+    };
+
+    let request = SessionRequest {
+        chat_id,
+        anchors,
+        provider_url,
+        api_key,
+        model: None,
+        speed,
+        temperature: None,
+        max_tokens: None,
+        top_p: None,
+        profile_id: None,
+        history_strategy: None,
+        pacing,
+        generation: None,
+        preset_id: None,
+        handle_x,
+        handle_y,
+        continue_message_id: Some(message_id),
+    };
+    run_mixing_session(app, db, session, request).await
+}
+
+/// Sampling settings a session runs with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sampling {
     temperature: f64,
     max_tokens: i64,
     top_p: f64,
+}
+
+/// Resolves each sampling setting from, in order: the call, the chat's saved
+/// value, the active preset's defaults, the global setting and the built-in
+/// default. When `preset_picked` (the run picks a different preset than the
+/// chat was last run with) the preset's defaults come before the chat's.
+/// `call` is `(temperature, max_tokens, top_p)`.
+fn resolve_sampling(
+    conn: &rusqlite::Connection,
+    call: (Option<f64>, Option<i64>, Option<f64>),
+    preset: &PresetDefaults,
+    chat: &ChatSettings,
+    preset_picked: bool,
+) -> Result<Sampling, String> {
+    fn global<T: std::str::FromStr>(conn: &rusqlite::Connection, key: &str) -> Result<Option<T>, String> {
+        Ok(load_setting(conn, key)?.and_then(|v| v.trim().parse().ok()))
+    }
+
+    let preset_values = (preset.temperature, preset.max_tokens, preset.top_p);
+    let chat_values = (chat.temperature, chat.max_tokens, chat.top_p);
+    let (first, second) = if preset_picked {
+        (preset_values, chat_values)
+    } else {
+        (chat_values, preset_values)
+    };
+    let (temperature, max_tokens, top_p) = call;
+    Ok(Sampling {
+        temperature: match temperature.or(first.0).or(second.0) {
+            Some(t) => t,
+            None => global(conn, "temperature")?.unwrap_or(DEFAULT_TEMPERATURE),
+        },
+        max_tokens: match max_tokens.or(first.1).or(second.1) {
+            Some(m) => m,
+            None => global(conn, "max_tokens")?.unwrap_or(DEFAULT_MAX_TOKENS),
+        },
+        top_p: match top_p.or(first.2).or(second.2) {
+            Some(p) => p,
+            None => global(conn, "top_p")?.unwrap_or(DEFAULT_TOP_P),
+        },
+    })
+}
+
+/// The model a session runs with: the one asked for, else the chat's saved
+/// model, else the global default, so a continue resolves like a send.
+fn resolve_model(
+    conn: &rusqlite::Connection,
+    model: Option<String>,
+    chat_settings: &ChatSettings,
+) -> Result<Option<String>, String> {
+    match model.or_else(|| chat_settings.model.clone()) {
+        Some(m) => Ok(Some(m)),
+        None => Ok(load_setting(conn, "model")?.filter(|m| !m.is_empty())),
+    }
+}
+
+async fn run_mixing_session(
+    app: AppHandle,
+    db: State<'_, Database>,
+    session: State<'_, SessionState>,
+    request: SessionRequest,
 ) -> Result<(), String> {
-    // Load conversation history from DB
-    let history = load_messages_from_db(&db, chat_id)?;
-    let history_json = messages_to_json(&history);
+    let SessionRequest {
+        chat_id,
+        anchors,
+        provider_url,
+        api_key,
+        model,
+        speed,
+        temperature: call_temperature,
+        max_tokens: call_max_tokens,
+        top_p: call_top_p,
+        profile_id,
+        history_strategy,
+        pacing,
+        generation,
+        preset_id,
+        handle_x,
+        handle_y,
+        continue_message_id,
+    } = request;
+    // Before anything is called or saved, so a bad weight costs nothing
+    if let Some(ref anchors) = anchors {
+        check_weights(anchors.iter().map(|a| a.weight))?;
+    }
+    let anchors_given = anchors.is_some();
+
+    let (mut chat_settings, anchors, preset_defaults, model, sampling) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let chat_settings = load_chat_settings(&conn, chat_id)?.unwrap_or_default();
+        let model = resolve_model(&conn, model, &chat_settings)?;
+        let anchors = match anchors {
+            Some(a) => a,
+            None => anchors_from_chat_settings(&conn, &chat_settings)?,
+        };
+        let preset_defaults = match (preset_id, chat_settings.preset_id) {
+            (Some(id), _) => load_preset_defaults(&conn, id)?,
+            // The preset saved with the chat may have been deleted since
+            (None, Some(id)) => load_preset_defaults(&conn, id).ok().flatten(),
+            (None, None) => None,
+        };
+        let preset_defaults = preset_defaults.unwrap_or_default();
+        let sampling = resolve_sampling(
+            &conn,
+            (call_temperature, call_max_tokens, call_top_p),
+            &preset_defaults,
+            &chat_settings,
+            preset_id.is_some() && preset_id != chat_settings.preset_id,
+        )?;
+        (chat_settings, anchors, preset_defaults, model, sampling)
+    };
 
-    // Build the mixed prompt
-    let composite_prompt = {
-        let active: Vec<&WeightedAnchorInput> =
-            anchors.iter().filter(|a| a.weight > 0.0).collect();
-
-        if active.len() == 1 {
-            active[0].prompt.clone()
-        } else if active.is_empty() {
-            return Err("No active anchors (all weights are 0)".to_string());
+    let pacing = PacingConfig::resolve(pacing, speed.as_deref(), preset_defaults.chunk_tokens)?;
+
+    let model = model.ok_or_else(|| "No model configured for this chat".to_string())?;
+    let Sampling {
+        temperature,
+        max_tokens,
+        top_p,
+    } = sampling;
+    let PresetDefaults {
+        meta_prompt,
+        mix_strategy,
+        ..
+    } = preset_defaults;
+    let mix_strategy = mix_strategy.unwrap_or_default();
+    let profile_id = profile_id.or(chat_settings.profile_id);
+    let generation = generation.or_else(|| chat_settings.generation.clone());
+
+    // Load conversation history and the model profile from DB
+    let mut history = load_messages_from_db(&db, chat_id)?;
+
+    // A continued message becomes the seed of generated_text, and only what
+    // came before it is history
+    let seed = match continue_message_id {
+        Some(id) => {
+            let pos = history
+                .iter()
+                .position(|m| m.id == id)
+                .ok_or_else(|| format!("Message {} not found in chat {}", id, chat_id))?;
+            if history[pos].role != "assistant" {
+                return Err("Only assistant messages can be continued".to_string());
+            }
+            let seed = history[pos].content.clone();
+            history.truncate(pos);
+            seed
+        }
+        None => String::new(),
+    };
+
+    let (base_instructions, history) = split_system_messages(
+        chat_settings.system_prompt.as_deref(),
+        history,
+        chat_settings.system_messages.unwrap_or_default(),
+    );
+    let (profile, attachment_data) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let attachment_data = if history.iter().any(|m| !m.attachments.is_empty()) {
+            load_attachment_data(&conn, chat_id)?
         } else {
-            // Call LLM to mix prompts
-            let client = new_llm_client()?;
-            let mix_prompt_text = build_mix_prompt(&anchors);
-            let mix_messages = vec![serde_json::json!({
-                "role": "user",
-                "content": mix_prompt_text,
-            })];
-
-            call_llm(
-                &client,
-                &provider_url,
-                &api_key,
-                &model,
-                &mix_messages,
-                512,
-                0.7,
-                1.0,
-            )
-            .await?
+            HashMap::new()
+        };
+        (resolve_profile(&conn, profile_id, &model)?, attachment_data)
+    };
+
+    // Per-call generation parameters override the profile's
+    let chat_generation = generation.clone();
+    let generation = match (generation, profile.as_ref()) {
+        (Some(g), Some(p)) => g.or(&p.generation),
+        (Some(g), None) => g,
+        (None, Some(p)) => p.generation.clone(),
+        (None, None) => GenerationParams::default(),
+    };
+    generation.validate()?;
+
+    // Remember what this chat was run with, so reopening it picks the same setup
+    {
+        chat_settings.profile_id = profile.as_ref().map(|p| p.id);
+        chat_settings.model = Some(model.clone());
+        // The resolved values, so later preset or global changes don't
+        // change what reopening the chat runs with
+        chat_settings.temperature = Some(temperature);
+        chat_settings.max_tokens = Some(max_tokens);
+        chat_settings.top_p = Some(top_p);
+        chat_settings.generation = chat_generation;
+        if preset_id.is_some() {
+            chat_settings.preset_id = preset_id;
+        }
+        // New weights replace the saved handle too, so the two keep matching
+        if anchors_given {
+            chat_settings.anchor_weights =
+                Some(anchors.iter().map(|a| (a.label.clone(), a.signed_weight())).collect());
+            chat_settings.handle_x = handle_x;
+            chat_settings.handle_y = handle_y;
+        }
+
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        save_chat_settings(&conn, chat_id, &chat_settings)?;
+    }
+
+    let max_loop_tokens: i64 = max_tokens.min(2048);
+    let scope = CallScope {
+        chat_id: Some(chat_id),
+        session_id: Some(uuid::Uuid::new_v4().to_string()),
+    };
+
+    // Build the mixed prompt
+    let composite_prompt = if let Some(prompt) = local_mix(&anchors, mix_strategy)? {
+        prompt
+    } else {
+        // Call LLM to mix prompts
+        let client = new_llm_client()?;
+        let mix_prompt_text = build_mix_prompt(&anchors, meta_prompt.as_deref());
+        let mix_messages = vec![serde_json::json!({
+            "role": "user",
+            "content": mix_prompt_text,
+        })];
+
+        let result = call_llm(
+            &client,
+            &provider_url,
+            &api_key,
+            &model,
+            &mix_messages,
+            MIX_MAX_TOKENS,
+            0.7,
+            1.0,
+            &mix_generation_params(&generation),
+        )
+        .await;
+
+        record_usage(&db, &scope, CALL_KIND_MIX, &model, &mix_messages, &result);
+        result?.content
+    };
+
+    let directives = anchor_directives(&anchors);
+    let examples = few_shot_messages(&anchors);
+
+    // Fit the history into the profile's context window, if one is configured
+    let history_json = match profile {
+        Some(ref p) => {
+            // Remixes may produce a longer prompt, so reserve at least a full mix
+            let reserved = count_tokens(&model, &composite_prompt).max(MIX_MAX_TOKENS as usize)
+                + count_tokens(&model, EXTRA_INSTRUCTIONS)
+                + count_tokens(&model, &base_instructions)
+                + anchor_content_tokens(&model, &anchors)
+                + MESSAGE_OVERHEAD_TOKENS
+                + REPLY_PRIMING_TOKENS
+                + max_loop_tokens as usize;
+            let budget = (p.context_window.max(0) as usize).saturating_sub(reserved);
+            let strategy = history_strategy.unwrap_or_default();
+            let plan = context::fit_history(&model, history, budget, &strategy);
+
+            let mut json = Vec::new();
+            if matches!(strategy, HistoryStrategy::Summarize) && !plan.dropped.is_empty() {
+                match summarize_history(
+                    &db,
+                    &scope,
+                    &provider_url,
+                    &api_key,
+                    &model,
+                    plan.dropped,
+                    budget,
+                )
+                .await
+                {
+                    Ok(summary) => json.push(serde_json::json!({
+                        "role": "system",
+                        "content": format!("Summary of the earlier conversation:\n{}", summary),
+                    })),
+                    // Without a summary the older turns are simply dropped
+                    Err(e) => eprintln!("Failed to summarize history: {}", e),
+                }
+            }
+            json.extend(messages_to_json(&plan.kept, &attachment_data));
+            json
         }
+        None => messages_to_json(&history, &attachment_data),
+    };
+
+    let (reasoning_delimiters, reasoning_field) = match profile {
+        Some(p) => (p.reasoning_delimiters, p.reasoning_field),
+        None => (reasoning::default_delimiters(), None),
     };
 
     // Set up cancel token
@@ -339,12 +1583,20 @@ pub async fn start_mixing_session(
     {
         let mut session_guard = session.0.lock().await;
         *session_guard = Some(MixingSession {
+            session_id: scope.session_id.clone().unwrap_or_default(),
+            chat_id,
             is_active: true,
             is_paused: false,
             is_mixing: false,
-            midtoken_sleep_ms: if speed == "fast" { 0 } else { 500 },
+            pacing,
+            remix_slowdown_left: 0,
+            generation: generation.clone(),
+            meta_prompt,
+            mix_strategy,
             current_promx: composite_prompt.clone(),
-            generated_text: String::new(),
+            directives,
+            examples,
+            generated_text: seed.clone(),
             cancel_token: cancel_tx,
         });
     }
@@ -363,8 +1615,12 @@ pub async fn start_mixing_session(
             }
         };
         let mut token_count: i64 = 0;
-        let mut in_think_block = false;
-        let max_loop_tokens: i64 = max_tokens.min(2048);
+        let mut reasoning_filter = ReasoningFilter::new(reasoning_delimiters);
+        let mut visible_text = seed;
+        let mut reasoning_text = String::new();
+        // Generated text that may be the start of a stop sequence, already in
+        // the model's context but not yet shown
+        let mut held_back = String::new();
 
         loop {
             // Check cancel
@@ -402,80 +1658,144 @@ pub async fn start_mixing_session(
             }
 
             // Build the messages array for the LLM call
-            let (current_promx, generated_text, sleep_ms) = {
-                let guard = session_arc.lock().await;
+            let (current_promx, directives, examples, generated_text, pacing, slow_after_remix) = {
+                let mut guard = session_arc.lock().await;
                 match *guard {
-                    Some(ref s) => (
-                        s.current_promx.clone(),
-                        s.generated_text.clone(),
-                        s.midtoken_sleep_ms,
-                    ),
+                    Some(ref mut s) => {
+                        let slow_after_remix = s.remix_slowdown_left > 0;
+                        if slow_after_remix {
+                            s.remix_slowdown_left -= 1;
+                        }
+                        (
+                            s.current_promx.clone(),
+                            s.directives.clone(),
+                            s.examples.clone(),
+                            s.generated_text.clone(),
+                            s.pacing.clone(),
+                            slow_after_remix,
+                        )
+                    }
                     None => break,
                 }
             };
 
-            // System message with composite prompt + extra instructions (must be first)
+            // System message with base instructions + composite prompt + extra instructions (must be first)
             let system_content =
-                format!("{}{}", current_promx, EXTRA_INSTRUCTIONS);
-            let mut call_messages = vec![serde_json::json!({
+                compose_system_prompt(&base_instructions, &current_promx, &directives);
+            let system_message = serde_json::json!({
                 "role": "system",
                 "content": system_content,
-            })];
-
-            // Append conversation history
-            call_messages.extend(history_json.clone());
+            });
 
             // Add accumulated assistant text if any
-            if !generated_text.is_empty() {
-                call_messages.push(serde_json::json!({
+            let generated_message = (!generated_text.is_empty()).then(|| {
+                serde_json::json!({
                     "role": "assistant",
                     "content": generated_text,
-                }));
-            }
+                })
+            });
+
+            // Few-shot examples, then the conversation history, borrowed so
+            // attachment contents aren't copied on every step
+            let call_messages: Vec<&serde_json::Value> = std::iter::once(&system_message)
+                .chain(&examples)
+                .chain(&history_json)
+                .chain(&generated_message)
+                .collect();
+
+            // Call LLM for the next chunk, abandoning it if the session is cancelled
+            let started = std::time::Instant::now();
+            let mut cancelled = cancel_rx.clone();
+            let result = tokio::select! {
+                result = call_llm(
+                    &client,
+                    &provider_url,
+                    &api_key,
+                    &model,
+                    &call_messages,
+                    pacing.chunk_tokens,
+                    temperature,
+                    top_p,
+                    &generation,
+                ) => result,
+                _ = cancelled.wait_for(|c| *c) => {
+                    record_cancelled_call(
+                        &app_handle.state::<Database>(),
+                        &scope,
+                        CALL_KIND_CONTINUATION,
+                        &model,
+                        &call_messages,
+                        started.elapsed().as_millis() as i64,
+                    );
+                    break;
+                }
+            };
 
-            // Call LLM with max_tokens=2
-            let result = call_llm(
-                &client,
-                &provider_url,
-                &api_key,
+            record_usage(
+                &app_handle.state::<Database>(),
+                &scope,
+                CALL_KIND_CONTINUATION,
                 &model,
                 &call_messages,
-                2,
-                temperature,
-                top_p,
-            )
-            .await;
+                &result,
+            );
 
             match result {
-                Ok(new_text) => {
-                    if new_text.is_empty() {
+                Ok(reply) => {
+
+                    let mut sleep_ms = pacing.step_delay_ms(reply.latency_ms);
+                    if slow_after_remix {
+                        sleep_ms += pacing.remix_slowdown_ms;
+                    }
+
+                    token_count += reply.completion_tokens.unwrap_or(pacing.chunk_tokens);
+
+                    let field_reasoning = reply
+                        .reasoning(reasoning_field.as_deref())
+                        .map(|r| r.to_string());
+                    let mut new_text = reply.content;
+                    if new_text.is_empty() && field_reasoning.is_none() {
                         break;
                     }
 
-                    token_count += 2;
+                    // With tiny chunks a stop sequence often straddles two calls,
+                    // which the provider never sees, so check across the boundary too
+                    let held_len = held_back.len();
+                    new_text.insert_str(0, &std::mem::take(&mut held_back));
+                    let mut stopped = false;
+                    if let Some(ref stops) = generation.stop {
+                        stopped = reply.finish_reason.as_deref() == Some("stop");
+                        if let Some(cut) = stop_sequence_cut(&new_text, stops) {
+                            new_text.truncate(cut);
+                            stopped = true;
+                        }
+                    }
 
-                    // Append full text (including think tags) to generated_text for model context
+                    // Append full text (including reasoning tags) to generated_text for model context
                     {
                         let mut guard = session_arc.lock().await;
                         if let Some(ref mut s) = *guard {
+                            let kept = s.generated_text.len() - held_len;
+                            s.generated_text.truncate(kept);
                             s.generated_text.push_str(&new_text);
                         }
                     }
 
-                    // Filter out <think>...</think> blocks for display
-                    let display_text = strip_think_tags(&new_text, &mut in_think_block);
-
-                    // Emit token event (only non-empty filtered text)
-                    if !display_text.is_empty() {
-                        let _ = app_handle.emit(
-                            "llm:token",
-                            TokenPayload {
-                                text: display_text,
-                            },
-                        );
+                    if let (false, Some(stops)) = (stopped, &generation.stop) {
+                        let hold = stop_prefix_len(&new_text, stops);
+                        held_back = new_text.split_off(new_text.len() - hold);
                     }
 
-                    if token_count >= max_loop_tokens {
+                    // Move reasoning blocks out of the displayed text
+                    let mut filtered = reasoning_filter.push(&new_text);
+                    if let Some(r) = field_reasoning {
+                        filtered.hidden.insert_str(0, &r);
+                    }
+                    visible_text.push_str(&filtered.visible);
+                    reasoning_text.push_str(&filtered.hidden);
+                    emit_filtered(&app_handle, filtered);
+
+                    if stopped || token_count >= max_loop_tokens {
                         break;
                     }
 
@@ -488,7 +1808,7 @@ pub async fn start_mixing_session(
                     let _ = app_handle.emit(
                         "llm:error",
                         StatusPayload {
-                            status: e,
+                            status: e.message,
                         },
                     );
                     break;
@@ -501,6 +1821,31 @@ pub async fn start_mixing_session(
             }
         }
 
+        // Release anything held back as a possible partial stop or tag
+        let mut filtered = reasoning_filter.push(&held_back);
+        let rest = reasoning_filter.finish();
+        filtered.visible.push_str(&rest.visible);
+        filtered.hidden.push_str(&rest.hidden);
+        visible_text.push_str(&filtered.visible);
+        reasoning_text.push_str(&filtered.hidden);
+        emit_filtered(&app_handle, filtered);
+
+        // A continued message is written back here; new replies are saved by the frontend
+        if let Some(message_id) = continue_message_id {
+            let db = app_handle.state::<Database>();
+            let saved = db
+                .0
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|mut conn| {
+                    replace_message_content(&mut conn, message_id, &visible_text)?;
+                    append_message_reasoning(&conn, message_id, &reasoning_text)
+                });
+            if let Err(e) = saved {
+                eprintln!("Failed to save continued message {}: {}", message_id, e);
+            }
+        }
+
         // Mark session inactive
         {
             let mut guard = session_arc.lock().await;
@@ -520,23 +1865,90 @@ pub async fn start_mixing_session(
     Ok(())
 }
 
+/// The stored session if it is still generating for `chat_id`.
+fn running_session(
+    session: &mut Option<MixingSession>,
+    chat_id: Option<i64>,
+) -> Option<&mut MixingSession> {
+    session
+        .as_mut()
+        .filter(|s| s.is_active && chat_id == Some(s.chat_id))
+}
+
+/// The stored session if it is still the one with `session_id`.
+fn same_session<'a>(
+    session: &'a mut Option<MixingSession>,
+    session_id: &Option<String>,
+) -> Option<&'a mut MixingSession> {
+    session
+        .as_mut()
+        .filter(|s| session_id.as_ref() == Some(&s.session_id))
+}
+
+/// Remixes the prompt for `chat_id`, and the prompt of its session if one is
+/// running. On success the weights (plus the preset and handle position, if
+/// given) are saved to that chat.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_weights(
     app: AppHandle,
+    db: State<'_, Database>,
     session: State<'_, SessionState>,
+    chat_id: Option<i64>,
     anchors: Vec<WeightedAnchorInput>,
     provider_url: String,
     api_key: String,
     model: String,
+    preset_id: Option<i64>,
+    handle_x: Option<f64>,
+    handle_y: Option<f64>,
 ) -> Result<(), String> {
-    // Set is_mixing = true if session is active
-    {
+    check_weights(anchors.iter().map(|a| a.weight))?;
+
+    // A given preset's meta-prompt and strategy win over the session's
+    let preset_defaults = match preset_id {
+        Some(id) => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            Some(load_preset_defaults(&conn, id)?.unwrap_or_default())
+        }
+        None => None,
+    };
+
+    // Set is_mixing = true if this chat's session is running
+    let (scope, mix_params, meta_prompt, mix_strategy) = {
         let mut guard = session.0.lock().await;
-        if let Some(ref mut s) = *guard {
-            s.is_mixing = true;
+        match running_session(&mut guard, chat_id) {
+            Some(s) => {
+                s.is_mixing = true;
+                if let Some(d) = preset_defaults {
+                    s.meta_prompt = d.meta_prompt;
+                    s.mix_strategy = d.mix_strategy.unwrap_or_default();
+                }
+                (
+                    CallScope {
+                        chat_id: Some(s.chat_id),
+                        session_id: Some(s.session_id.clone()),
+                    },
+                    mix_generation_params(&s.generation),
+                    s.meta_prompt.clone(),
+                    s.mix_strategy,
+                )
+            }
+            // Otherwise we still mix, for the chat alone
+            None => {
+                let d = preset_defaults.unwrap_or_default();
+                (
+                    CallScope {
+                        chat_id,
+                        session_id: None,
+                    },
+                    GenerationParams::default(),
+                    d.meta_prompt,
+                    d.mix_strategy.unwrap_or_default(),
+                )
+            }
         }
-        // If None, we proceed to allow mixing even without an active session
-    }
+    };
 
     let _ = app.emit(
         "llm:status",
@@ -545,45 +1957,80 @@ pub async fn update_weights(
         },
     );
 
-    let active: Vec<&WeightedAnchorInput> = anchors.iter().filter(|a| a.weight > 0.0).collect();
-
-    let new_promx = if active.is_empty() {
-        // Reset mixing flag before returning error
-        let mut guard = session.0.lock().await;
-        if let Some(ref mut s) = *guard {
-            s.is_mixing = false;
+    let local = match local_mix(&anchors, mix_strategy) {
+        Ok(local) => local,
+        Err(e) => {
+            // Reset mixing flag before returning error
+            let mut guard = session.0.lock().await;
+            if let Some(s) = same_session(&mut guard, &scope.session_id) {
+                s.is_mixing = false;
+            }
+            return Err(e);
         }
-        return Err("No active anchors (all weights are 0)".to_string());
-    } else if active.len() == 1 {
-        active[0].prompt.clone()
+    };
+
+    let new_promx = if let Some(prompt) = local {
+        prompt
     } else {
         let client = new_llm_client()?;
-        let mix_prompt_text = build_mix_prompt(&anchors);
+        let mix_prompt_text = build_mix_prompt(&anchors, meta_prompt.as_deref());
         let mix_messages = vec![serde_json::json!({
             "role": "user",
             "content": mix_prompt_text,
         })];
 
-        match call_llm(&client, &provider_url, &api_key, &model, &mix_messages, 512, 0.7, 1.0)
-            .await
-        {
-            Ok(result) => result,
+        let result = call_llm(
+            &client,
+            &provider_url,
+            &api_key,
+            &model,
+            &mix_messages,
+            MIX_MAX_TOKENS,
+            0.7,
+            1.0,
+            &mix_params,
+        )
+        .await;
+        record_usage(&db, &scope, CALL_KIND_MIX, &model, &mix_messages, &result);
+
+        match result {
+            Ok(reply) => reply.content,
             Err(e) => {
                 let mut guard = session.0.lock().await;
-                if let Some(ref mut s) = *guard {
+                if let Some(s) = same_session(&mut guard, &scope.session_id) {
                     s.is_mixing = false;
                 }
-                return Err(e);
+                return Err(e.into());
             }
         }
     };
 
-    // Update session with new prompt if active
+    // Update the session with the new prompt if it is still the one we mixed for
     {
         let mut guard = session.0.lock().await;
-        if let Some(ref mut s) = *guard {
+        if let Some(s) = same_session(&mut guard, &scope.session_id) {
             s.current_promx = new_promx;
+            s.directives = anchor_directives(&anchors);
+            s.examples = few_shot_messages(&anchors);
             s.is_mixing = false;
+            s.remix_slowdown_left = s.pacing.remix_slowdown_steps;
+        }
+    }
+
+    if let Some(chat_id) = chat_id {
+        let state = ChatMixerState {
+            preset_id,
+            handle_x,
+            handle_y,
+            weights: anchors.iter().map(|a| (a.label.clone(), a.signed_weight())).collect(),
+        };
+        let saved = db
+            .0
+            .lock()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| save_chat_mixer_state(&conn, chat_id, &state));
+        if let Err(e) = saved {
+            eprintln!("Failed to save mixer state for chat {}: {}", chat_id, e);
         }
     }
 
@@ -614,11 +2061,26 @@ pub async fn set_speed(session: State<'_, SessionState>, speed: String) -> Resul
     let mut guard = session.0.lock().await;
     match *guard {
         Some(ref mut s) => {
-            s.midtoken_sleep_ms = match speed.as_str() {
-                "slow" => 500,
-                "fast" => 0,
-                _ => return Err(format!("Unknown speed: {}. Use 'slow' or 'fast'.", speed)),
-            };
+            let preset = PacingConfig::from_speed(&speed)?;
+            s.pacing.delay_ms = preset.delay_ms;
+            s.pacing.tokens_per_second = None;
+            Ok(())
+        }
+        None => Err("No active session".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn set_pacing(
+    session: State<'_, SessionState>,
+    pacing: PacingConfig,
+) -> Result<(), String> {
+    pacing.validate()?;
+
+    let mut guard = session.0.lock().await;
+    match *guard {
+        Some(ref mut s) => {
+            s.pacing = pacing;
             Ok(())
         }
         None => Err("No active session".to_string()),
@@ -636,3 +2098,462 @@ pub async fn cancel_session(session: State<'_, SessionState>) -> Result<(), Stri
         None => Err("No active session".to_string()),
     }
 }
+
+/// Names a chat after its first exchange using `model` (or the chat's saved
+/// model). Falls back to the first user message cut at 50 characters when no
+/// model is available or the call fails. Emits `chat:title`.
+#[tauri::command]
+pub async fn generate_chat_title(
+    app: AppHandle,
+    db: State<'_, Database>,
+    chat_id: i64,
+    provider_url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<String, String> {
+    let (opening, saved_model) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT role, content FROM messages
+                 WHERE chat_id = ?1 AND deleted_at IS NULL AND role IN ('user', 'assistant')
+                 ORDER BY id ASC
+                 LIMIT 2",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let rows = stmt
+            .query_map(rusqlite::params![chat_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut opening = Vec::new();
+        for row in rows {
+            opening.push(row.map_err(|e| e.to_string())?);
+        }
+
+        let saved_model = load_chat_settings(&conn, chat_id)?.and_then(|s| s.model);
+        (opening, saved_model)
+    };
+
+    let first_user = opening
+        .iter()
+        .find(|(role, _)| role == "user")
+        .map(|(_, content)| content.split_whitespace().collect::<Vec<_>>().join(" "))
+        .ok_or_else(|| "Chat has no user message to title".to_string())?;
+
+    let mut generated = None;
+    if let Some(model) = model.or(saved_model) {
+        let transcript = opening
+            .iter()
+            .map(|(role, content)| {
+                format!(
+                    "{}: {}",
+                    role,
+                    truncate_at_word_boundary(content, TITLE_EXCERPT_CHARS)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let client = new_llm_client()?;
+        let title_messages = vec![serde_json::json!({
+            "role": "user",
+            "content": TITLE_PROMPT.replace("{transcript}", &transcript),
+        })];
+
+        let result = call_llm(
+            &client,
+            &provider_url,
+            &api_key,
+            &model,
+            &title_messages,
+            TITLE_MAX_TOKENS,
+            0.3,
+            1.0,
+            &GenerationParams::default(),
+        )
+        .await;
+        let scope = CallScope {
+            chat_id: Some(chat_id),
+            session_id: None,
+        };
+        record_usage(&db, &scope, CALL_KIND_TITLE, &model, &title_messages, &result);
+
+        match result {
+            Ok(reply) => generated = clean_title(&reply.content),
+            Err(e) => eprintln!("Failed to generate title for chat {}: {}", chat_id, e),
+        }
+    }
+
+    let title =
+        generated.unwrap_or_else(|| truncate_at_word_boundary(&first_user, TITLE_FALLBACK_CHARS));
+
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE chats SET title = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![&title, models::now(), chat_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let _ = app.emit(
+        "chat:title",
+        ChatTitlePayload {
+            chat_id,
+            title: title.clone(),
+        },
+    );
+
+    Ok(title)
+}
+
+/// Asks `model` (or the model from settings) for `count` anchors themed
+/// around `description`, lays them out in a circle and saves them as a new
+/// user preset. The preset is called `name`, or what the model suggests,
+/// numbered if taken.
+#[tauri::command]
+pub async fn generate_preset(
+    db: State<'_, Database>,
+    description: String,
+    count: usize,
+    name: Option<String>,
+    provider_url: String,
+    api_key: String,
+    model: Option<String>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if description.is_empty() {
+        return Err("Describe the preset to generate".to_string().into());
+    }
+    if !(GENERATE_PRESET_MIN_ANCHORS..=GENERATE_PRESET_MAX_ANCHORS).contains(&count) {
+        return Err(format!(
+            "Anchor count must be between {} and {}",
+            GENERATE_PRESET_MIN_ANCHORS, GENERATE_PRESET_MAX_ANCHORS
+        )
+        .into());
+    }
+
+    let model = match model {
+        Some(m) => m,
+        None => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            load_setting(&conn, "model")?
+                .filter(|m| !m.is_empty())
+                .ok_or_else(|| "No model configured".to_string())?
+        }
+    };
+
+    let client = new_llm_client()?;
+    let preset_messages = vec![serde_json::json!({
+        "role": "user",
+        "content": GENERATE_PRESET_PROMPT
+            .replace("{count}", &count.to_string())
+            .replace("{description}", &description),
+    })];
+
+    let result = call_llm(
+        &client,
+        &provider_url,
+        &api_key,
+        &model,
+        &preset_messages,
+        GENERATE_PRESET_TOKENS_PER_ANCHOR * count as i64,
+        0.9,
+        1.0,
+        &GenerationParams::default(),
+    )
+    .await;
+
+    let scope = CallScope {
+        chat_id: None,
+        session_id: None,
+    };
+    record_usage(&db, &scope, CALL_KIND_PRESET, &model, &preset_messages, &result);
+    let reply = result.map_err(String::from)?;
+
+    let generated = parse_generated_preset(&reply.content)?;
+    let anchors = generated_anchors(generated.anchors, count)?;
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let name = match name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+        Some(n) => n,
+        None => {
+            let suggested = generated.name.trim();
+            let base = if suggested.is_empty() {
+                truncate_at_word_boundary(&description, TITLE_FALLBACK_CHARS)
+            } else {
+                truncate_at_word_boundary(suggested, TITLE_FALLBACK_CHARS)
+            };
+            unique_preset_name(&conn, &base)?
+        }
+    };
+
+    save_new_preset(&mut conn, name, anchors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(chat_id: i64, is_active: bool) -> Option<MixingSession> {
+        Some(MixingSession {
+            session_id: format!("session-{}", chat_id),
+            chat_id,
+            is_active,
+            is_paused: false,
+            is_mixing: false,
+            pacing: PacingConfig::from_speed("fast").unwrap(),
+            remix_slowdown_left: 0,
+            generation: GenerationParams::default(),
+            meta_prompt: None,
+            mix_strategy: MixStrategy::Auto,
+            current_promx: String::new(),
+            directives: String::new(),
+            examples: Vec::new(),
+            generated_text: String::new(),
+            cancel_token: tokio::sync::watch::channel(false).0,
+        })
+    }
+
+    #[test]
+    fn remixes_only_touch_a_running_session_of_the_same_chat() {
+        assert!(running_session(&mut session(1, true), Some(1)).is_some());
+        assert!(running_session(&mut session(1, true), Some(2)).is_none());
+        assert!(running_session(&mut session(1, true), None).is_none());
+        // A finished session no longer belongs to the chat being remixed
+        assert!(running_session(&mut session(1, false), Some(1)).is_none());
+        assert!(running_session(&mut None, Some(1)).is_none());
+    }
+
+    #[test]
+    fn a_replaced_session_is_left_alone() {
+        let mut stored = session(1, true);
+        assert!(same_session(&mut stored, &Some("session-1".to_string())).is_some());
+        assert!(same_session(&mut stored, &Some("session-2".to_string())).is_none());
+        assert!(same_session(&mut stored, &None).is_none());
+    }
+
+    #[test]
+    fn pacing_wins_over_speed() {
+        let given = PacingConfig {
+            chunk_tokens: 8,
+            delay_ms: Some(40),
+            tokens_per_second: None,
+            remix_slowdown_ms: 0,
+            remix_slowdown_steps: 0,
+        };
+        let pacing = PacingConfig::resolve(Some(given), None, Some(16)).unwrap();
+        assert_eq!(pacing.chunk_tokens, 8);
+        assert_eq!(pacing.delay_ms, Some(40));
+    }
+
+    #[test]
+    fn speed_takes_the_preset_chunk_size() {
+        let pacing = PacingConfig::resolve(None, Some("slow"), Some(16)).unwrap();
+        assert_eq!(pacing.chunk_tokens, 16);
+        assert_eq!(pacing.delay_ms, Some(500));
+    }
+
+    #[test]
+    fn pacing_needs_speed_or_config() {
+        assert!(PacingConfig::resolve(None, None, None).is_err());
+        assert!(PacingConfig::resolve(None, Some("warp"), None).is_err());
+        assert!(PacingConfig::resolve(None, Some("fast"), Some(0)).is_err());
+    }
+
+    #[test]
+    fn borrowed_messages_serialize_alongside_the_params() {
+        let history = vec![serde_json::json!({ "role": "user", "content": "Hi" })];
+        let system = serde_json::json!({ "role": "system", "content": "Be brief" });
+        let messages: Vec<&serde_json::Value> = std::iter::once(&system).chain(&history).collect();
+
+        let body = serde_json::to_value(CompletionRequest {
+            messages: &messages,
+            params: serde_json::json!({ "model": "m", "max_tokens": 8 }),
+        })
+        .unwrap();
+        assert_eq!(body["model"], "m");
+        assert_eq!(body["max_tokens"], 8);
+        assert_eq!(body["messages"][1]["content"], "Hi");
+        assert_eq!(estimate_prompt_tokens("m", &messages), estimate_prompt_tokens("m", &[system, history[0].clone()]));
+    }
+
+    #[test]
+    fn a_chat_without_a_model_falls_back_to_the_global_one() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let chat = ChatSettings::default();
+        assert_eq!(resolve_model(&conn, None, &chat).unwrap(), None);
+
+        conn.execute("INSERT INTO app_state (key, value) VALUES ('model', 'global')", [])
+            .unwrap();
+        assert_eq!(resolve_model(&conn, None, &chat).unwrap().as_deref(), Some("global"));
+
+        let chat = ChatSettings {
+            model: Some("saved".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_model(&conn, None, &chat).unwrap().as_deref(), Some("saved"));
+        assert_eq!(
+            resolve_model(&conn, Some("asked".to_string()), &chat).unwrap().as_deref(),
+            Some("asked")
+        );
+    }
+
+    #[test]
+    fn sampling_takes_the_call_then_a_picked_preset_then_chat_then_global() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let none = (None, None, None);
+
+        let defaults = resolve_sampling(&conn, none, &PresetDefaults::default(), &ChatSettings::default(), false).unwrap();
+        assert_eq!(
+            defaults,
+            Sampling {
+                temperature: DEFAULT_TEMPERATURE,
+                max_tokens: DEFAULT_MAX_TOKENS,
+                top_p: DEFAULT_TOP_P,
+            }
+        );
+
+        conn.execute(
+            "INSERT INTO app_state (key, value) VALUES ('temperature', '0.2'), ('max_tokens', '512'), ('top_p', 'oops')",
+            [],
+        )
+        .unwrap();
+        let global = resolve_sampling(&conn, none, &PresetDefaults::default(), &ChatSettings::default(), false).unwrap();
+        assert_eq!((global.temperature, global.max_tokens, global.top_p), (0.2, 512, DEFAULT_TOP_P));
+
+        let chat = ChatSettings {
+            temperature: Some(0.4),
+            max_tokens: Some(1024),
+            ..Default::default()
+        };
+        let preset = PresetDefaults {
+            temperature: Some(1.2),
+            ..Default::default()
+        };
+        let resolved = resolve_sampling(&conn, none, &preset, &chat, true).unwrap();
+        assert_eq!((resolved.temperature, resolved.max_tokens), (1.2, 1024));
+
+        let called = resolve_sampling(&conn, (Some(0.9), None, Some(0.5)), &preset, &chat, true).unwrap();
+        assert_eq!((called.temperature, called.max_tokens, called.top_p), (0.9, 1024, 0.5));
+
+        let reopened = resolve_sampling(&conn, none, &preset, &chat, false).unwrap();
+        assert_eq!((reopened.temperature, reopened.max_tokens), (0.4, 1024));
+    }
+
+    #[test]
+    fn a_reopened_chat_keeps_its_sampling_after_preset_defaults_change() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        conn.execute(
+            "INSERT INTO chats (title, created_at, updated_at) VALUES ('Chat', ?1, ?1)",
+            rusqlite::params![models::now()],
+        )
+        .unwrap();
+        let chat_id = conn.last_insert_rowid();
+        let mut presets = conn.prepare("SELECT id FROM anchor_presets ORDER BY id LIMIT 2").unwrap();
+        let ids: Vec<i64> = presets
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let set_temperature = |preset_id: i64, temperature: f64| {
+            let defaults = PresetDefaults {
+                temperature: Some(temperature),
+                ..Default::default()
+            };
+            conn.execute(
+                "UPDATE anchor_presets SET defaults = ?1 WHERE id = ?2",
+                rusqlite::params![serde_json::to_string(&defaults).unwrap(), preset_id],
+            )
+            .unwrap();
+        };
+        let run = |preset_id: i64| {
+            let chat = load_chat_settings(&conn, chat_id).unwrap().unwrap_or_default();
+            let preset = load_preset_defaults(&conn, preset_id).unwrap().unwrap_or_default();
+            resolve_sampling(&conn, (None, None, None), &preset, &chat, chat.preset_id != Some(preset_id))
+                .unwrap()
+        };
+
+        set_temperature(ids[0], 1.2);
+        let first = run(ids[0]);
+        assert_eq!(first.temperature, 1.2);
+        let saved = ChatSettings {
+            temperature: Some(first.temperature),
+            max_tokens: Some(first.max_tokens),
+            top_p: Some(first.top_p),
+            preset_id: Some(ids[0]),
+            ..Default::default()
+        };
+        save_chat_settings(&conn, chat_id, &saved).unwrap();
+
+        set_temperature(ids[0], 0.3);
+        assert_eq!(run(ids[0]), first);
+
+        set_temperature(ids[1], 0.7);
+        assert_eq!(run(ids[1]).temperature, 0.7);
+    }
+
+    #[test]
+    fn a_generated_preset_can_be_weighed_straight_away() {
+        let reply = r##"Here you go:
+```json
+{"name": "Weather", "anchors": [
+  {"label": "Sunny", "prompt": "Be bright.", "color": "#FFCC00", "icon": "*"},
+  {"label": "Stormy", "prompt": "Be wild.", "color": "#334455", "icon": "!!"},
+  {"label": "Foggy", "prompt": "Be vague.", "color": "#AABBCC", "icon": "~~"}
+]}
+```"##;
+        let generated = parse_generated_preset(reply).unwrap();
+        let anchors = generated_anchors(generated.anchors, 3).unwrap();
+
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let preset = save_new_preset(&mut conn, generated.name, anchors).unwrap();
+
+        let top = crate::weights::circle_layout(3)[0];
+        let weights = crate::commands::presets::preset_weights(
+            &conn,
+            preset.id,
+            top.0,
+            top.1,
+            crate::weights::Falloff::Linear,
+            crate::weights::Normalization::None,
+        )
+        .unwrap();
+        assert_eq!(weights["Sunny"], 1.0);
+        assert_eq!(weights.len(), 3);
+    }
+
+    #[test]
+    fn generated_icons_must_be_ascii() {
+        let reply = r##"{"name": "Weather", "anchors": [
+  {"label": "Sunny", "prompt": "Be bright.", "color": "#FFCC00", "icon": "☀"}
+]}"##;
+        let generated = parse_generated_preset(reply).unwrap();
+        let err = generated_anchors(generated.anchors, 1).unwrap_err();
+        assert!(err.contains("unusable icon"), "{}", err);
+    }
+
+    #[test]
+    fn a_stop_split_across_chunks_is_never_shown() {
+        let stops = vec!["END".to_string(), "ÉTÉ".to_string()];
+
+        let mut first = "Hello E".to_string();
+        assert_eq!(stop_sequence_cut(&first, &stops), None);
+        let held = first.split_off(first.len() - stop_prefix_len(&first, &stops));
+        assert_eq!((first.as_str(), held.as_str()), ("Hello ", "E"));
+
+        let second = format!("{}ND more", held);
+        assert_eq!(stop_sequence_cut(&second, &stops), Some(0));
+
+        assert_eq!(stop_prefix_len("Bonjour É", &stops), "É".len());
+        assert_eq!(stop_prefix_len("Hello", &stops), 0);
+    }
+}
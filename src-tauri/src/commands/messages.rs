@@ -1,31 +1,56 @@
+use rusqlite::Connection;
 use tauri::State;
 
+use crate::commands::attachments::load_attachments_by_message;
 use crate::db::Database;
-use crate::models::{self, Message};
+use crate::models::{self, Message, MessageRevision};
 
+/// Returns a chat's messages in order. With `limit`, only the newest
+/// `limit` messages older than `before_id` (or the newest overall) are
+/// returned, so the UI can page backwards from the end.
 #[tauri::command]
-pub fn get_messages(db: State<Database>, chat_id: i64) -> Result<Vec<Message>, String> {
+pub fn get_messages(
+    db: State<Database>,
+    chat_id: i64,
+    before_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<Message>, String> {
+    if limit.is_some_and(|l| l <= 0) {
+        return Err("Limit must be positive".to_string());
+    }
+
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
         .prepare(
-            "SELECT id, chat_id, role, content, created_at
-             FROM messages
-             WHERE chat_id = ?1
+            "SELECT id, chat_id, role, content, reasoning, created_at
+             FROM (
+                 SELECT id, chat_id, role, content, reasoning, created_at
+                 FROM messages
+                 WHERE chat_id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR id < ?2)
+                 ORDER BY id DESC
+                 LIMIT ?3
+             )
              ORDER BY id ASC",
         )
         .map_err(|e| e.to_string())?;
 
+    // A negative LIMIT means no limit in SQLite
     let rows = stmt
-        .query_map(rusqlite::params![chat_id], |row| {
-            Ok(Message {
-                id: row.get(0)?,
-                chat_id: row.get(1)?,
-                role: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })
+        .query_map(
+            rusqlite::params![chat_id, before_id, limit.unwrap_or(-1)],
+            |row| {
+                Ok(Message {
+                    id: row.get(0)?,
+                    chat_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    reasoning: row.get(4)?,
+                    created_at: row.get(5)?,
+                    attachments: Vec::new(),
+                })
+            },
+        )
         .map_err(|e| e.to_string())?;
 
     let mut messages = Vec::new();
@@ -33,22 +58,54 @@ pub fn get_messages(db: State<Database>, chat_id: i64) -> Result<Vec<Message>, S
         messages.push(row.map_err(|e| e.to_string())?);
     }
 
+    let ids: Vec<i64> = messages.iter().map(|m| m.id).collect();
+    let mut attachments = load_attachments_by_message(&conn, &ids)?;
+    for message in &mut messages {
+        message.attachments = attachments.remove(&message.id).unwrap_or_default();
+    }
+
     Ok(messages)
 }
 
+/// IDs of a chat's messages in order, without their content.
+#[tauri::command]
+pub fn get_message_ids(db: State<Database>, chat_id: i64) -> Result<Vec<i64>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM messages
+             WHERE chat_id = ?1 AND deleted_at IS NULL
+             ORDER BY id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![chat_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(ids)
+}
+
 #[tauri::command]
 pub fn add_message(
     db: State<Database>,
     chat_id: i64,
     role: String,
     content: String,
+    reasoning: Option<String>,
 ) -> Result<Message, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
     let timestamp = models::now();
 
     conn.execute(
-        "INSERT INTO messages (chat_id, role, content, created_at) VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![chat_id, &role, &content, &timestamp],
+        "INSERT INTO messages (chat_id, role, content, reasoning, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![chat_id, &role, &content, &reasoning, &timestamp],
     )
     .map_err(|e| e.to_string())?;
 
@@ -65,10 +122,13 @@ pub fn add_message(
         chat_id,
         role,
         content,
+        reasoning,
         created_at: timestamp,
+        attachments: Vec::new(),
     })
 }
 
+/// Moves every message after `message_id` to the trash.
 #[tauri::command]
 pub fn delete_messages_after(
     db: State<Database>,
@@ -78,27 +138,222 @@ pub fn delete_messages_after(
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     conn.execute(
-        "DELETE FROM messages WHERE chat_id = ?1 AND id > ?2",
-        rusqlite::params![chat_id, message_id],
+        "UPDATE messages SET deleted_at = ?1 WHERE chat_id = ?2 AND id > ?3 AND deleted_at IS NULL",
+        rusqlite::params![models::now(), chat_id, message_id],
     )
     .map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
-#[tauri::command]
-pub fn update_message(
-    db: State<Database>,
+/// Adds reasoning produced while continuing a message after what it already
+/// had, so the continued message keeps all of its thinking.
+pub(crate) fn append_message_reasoning(
+    conn: &Connection,
     message_id: i64,
-    content: String,
+    reasoning: &str,
 ) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    if reasoning.is_empty() {
+        return Ok(());
+    }
 
     conn.execute(
+        "UPDATE messages
+         SET reasoning = CASE WHEN reasoning IS NULL OR reasoning = '' THEN ?1 ELSE reasoning || char(10) || ?1 END
+         WHERE id = ?2",
+        rusqlite::params![reasoning, message_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Replaces a message's content, keeping the old version as a revision and
+/// bumping the chat's `updated_at`.
+pub(crate) fn replace_message_content(
+    conn: &mut Connection,
+    message_id: i64,
+    content: &str,
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (chat_id, previous): (i64, String) = tx
+        .query_row(
+            "SELECT chat_id, content FROM messages WHERE id = ?1",
+            rusqlite::params![message_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Message {} not found", message_id),
+            e => e.to_string(),
+        })?;
+
+    if previous == content {
+        return Ok(());
+    }
+
+    let timestamp = models::now();
+
+    tx.execute(
+        "INSERT INTO message_revisions (message_id, content, created_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![message_id, &previous, &timestamp],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
         "UPDATE messages SET content = ?1 WHERE id = ?2",
-        rusqlite::params![&content, message_id],
+        rusqlite::params![content, message_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "UPDATE chats SET updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![&timestamp, chat_id],
     )
     .map_err(|e| e.to_string())?;
 
+    tx.commit().map_err(|e| e.to_string())?;
+
     Ok(())
 }
+
+#[tauri::command]
+pub fn update_message(
+    db: State<Database>,
+    message_id: i64,
+    content: String,
+) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    replace_message_content(&mut conn, message_id, &content)
+}
+
+/// Previous versions of a message, newest first.
+#[tauri::command]
+pub fn list_message_revisions(
+    db: State<Database>,
+    message_id: i64,
+) -> Result<Vec<MessageRevision>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, message_id, content, created_at
+             FROM message_revisions
+             WHERE message_id = ?1
+             ORDER BY id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![message_id], |row| {
+            Ok(MessageRevision {
+                id: row.get(0)?,
+                message_id: row.get(1)?,
+                content: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut revisions = Vec::new();
+    for row in rows {
+        revisions.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(revisions)
+}
+
+/// Puts a revision's content back into its message. The content being
+/// replaced is saved as a new revision, so a restore can be undone too.
+#[tauri::command]
+pub fn restore_message_revision(db: State<Database>, revision_id: i64) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let (message_id, content): (i64, String) = conn
+        .query_row(
+            "SELECT message_id, content FROM message_revisions WHERE id = ?1",
+            rusqlite::params![revision_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Revision {} not found", revision_id),
+            e => e.to_string(),
+        })?;
+
+    replace_message_content(&mut conn, message_id, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continued_reasoning_is_appended_to_the_message() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let now = models::now();
+        conn.execute(
+            "INSERT INTO chats (title, created_at, updated_at) VALUES ('Chat', ?1, ?1)",
+            rusqlite::params![now],
+        )
+        .unwrap();
+        let chat_id = conn.last_insert_rowid();
+        conn.execute(
+            "INSERT INTO messages (chat_id, role, content, created_at) VALUES (?1, 'assistant', 'Hi', ?2)",
+            rusqlite::params![chat_id, now],
+        )
+        .unwrap();
+        let message_id = conn.last_insert_rowid();
+        let reasoning = |conn: &Connection| -> Option<String> {
+            conn.query_row(
+                "SELECT reasoning FROM messages WHERE id = ?1",
+                rusqlite::params![message_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        append_message_reasoning(&conn, message_id, "").unwrap();
+        assert_eq!(reasoning(&conn), None);
+        append_message_reasoning(&conn, message_id, "first").unwrap();
+        append_message_reasoning(&conn, message_id, "second").unwrap();
+        assert_eq!(reasoning(&conn).as_deref(), Some("first\nsecond"));
+    }
+
+    #[test]
+    fn attachments_load_only_for_the_given_live_messages() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let now = models::now();
+        conn.execute(
+            "INSERT INTO chats (title, created_at, updated_at) VALUES ('Chat', ?1, ?1)",
+            rusqlite::params![now],
+        )
+        .unwrap();
+        let chat_id = conn.last_insert_rowid();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            conn.execute(
+                "INSERT INTO messages (chat_id, role, content, created_at) VALUES (?1, 'user', 'See file', ?2)",
+                rusqlite::params![chat_id, now],
+            )
+            .unwrap();
+            let message_id = conn.last_insert_rowid();
+            conn.execute(
+                "INSERT INTO attachments (message_id, file_name, mime_type, size_bytes, data, created_at)
+                 VALUES (?1, 'notes.txt', 'text/plain', 2, x'6869', ?2)",
+                rusqlite::params![message_id, now],
+            )
+            .unwrap();
+            ids.push(message_id);
+        }
+        conn.execute(
+            "UPDATE messages SET deleted_at = ?1 WHERE id = ?2",
+            rusqlite::params![now, ids[2]],
+        )
+        .unwrap();
+
+        let attachments = load_attachments_by_message(&conn, &ids[1..]).unwrap();
+        assert_eq!(attachments.keys().copied().collect::<Vec<_>>(), vec![ids[1]]);
+    }
+}
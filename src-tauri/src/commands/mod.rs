@@ -1,5 +1,12 @@
+pub mod anchors;
+pub mod attachments;
 pub mod chat;
+pub mod folders;
 pub mod llm;
 pub mod messages;
 pub mod presets;
+pub mod profiles;
 pub mod settings;
+pub mod tags;
+pub mod trash;
+pub mod usage;
@@ -1,25 +1,364 @@
+use std::collections::{BTreeMap, HashSet};
+
+use rusqlite::Connection;
 use tauri::State;
 
+use crate::builtin_presets;
+use crate::commands::anchors::load_library_anchor;
 use crate::db::Database;
-use crate::models::{self, AnchorPreset, NewPresetAnchor, PresetAnchor};
+use crate::weights::{self, Falloff, Normalization};
+use crate::models::{self, AnchorKind, AnchorPreset, FieldError, LibraryAnchor, MergeConflictPolicy, NewPresetAnchor, PresetAnchor, PresetDefaults, PresetWriteError};
+
+/// JSON for an anchor's `examples` or `items` column; an empty list is NULL.
+pub(crate) fn list_to_column<T: serde::Serialize>(list: &[T]) -> Result<Option<String>, String> {
+    if list.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(list)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+pub(crate) fn list_from_column<T: serde::de::DeserializeOwned>(value: Option<String>) -> Vec<T> {
+    value
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+pub(crate) fn kind_from_column(value: String) -> AnchorKind {
+    AnchorKind::parse(&value).unwrap_or_default()
+}
+
+/// Largest canvas coordinate or influence radius a preset anchor may store.
+const MAX_CANVAS_COORDINATE: f64 = 10_000.0;
+
+fn field_error(field: &str, anchor_index: Option<usize>, message: impl Into<String>) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        anchor_index,
+        message: message.into(),
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    let Some(hex) = color.strip_prefix('#') else {
+        return false;
+    };
+    matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Gives anchors placed from the library the library's text, icons, color
+/// and content. Library anchors that no longer exist are reported as field errors.
+fn resolve_library_anchors(
+    conn: &Connection,
+    anchors: Vec<NewPresetAnchor>,
+    errors: &mut Vec<FieldError>,
+) -> Vec<NewPresetAnchor> {
+    anchors
+        .into_iter()
+        .enumerate()
+        .map(|(i, anchor)| {
+            let Some(anchor_id) = anchor.anchor_id else {
+                return anchor;
+            };
+            match load_library_anchor(conn, anchor_id) {
+                Ok(library) => NewPresetAnchor {
+                    label: library.label,
+                    prompt: library.prompt,
+                    icon_small: library.icon_small,
+                    icon_large: library.icon_large,
+                    color: library.color,
+                    kind: library.kind,
+                    examples: library.examples,
+                    items: library.items,
+                    ..anchor
+                },
+                Err(e) => {
+                    errors.push(field_error("anchor_id", Some(i), e));
+                    anchor
+                }
+            }
+        })
+        .collect()
+}
+
+/// Checks what an anchor says, wherever it lives: its label, the content its
+/// kind needs, and its color. `anchor_index` is `None` for library anchors.
+fn validate_anchor_content(
+    anchor: &NewPresetAnchor,
+    anchor_index: Option<usize>,
+    errors: &mut Vec<FieldError>,
+) {
+    let i = anchor_index;
+    if anchor.label.trim().is_empty() {
+        errors.push(field_error("label", i, "Label cannot be empty"));
+    }
+    match anchor.kind {
+        AnchorKind::Persona => {
+            if anchor.prompt.trim().is_empty() {
+                errors.push(field_error("prompt", i, "Prompt cannot be empty"));
+            }
+        }
+        AnchorKind::FewShot => {
+            if anchor.examples.is_empty() {
+                errors.push(field_error("examples", i, "Add at least one example"));
+            } else if anchor
+                .examples
+                .iter()
+                .any(|e| e.user.trim().is_empty() || e.assistant.trim().is_empty())
+            {
+                errors.push(field_error(
+                    "examples",
+                    i,
+                    "Every example needs both a user and an assistant message",
+                ));
+            }
+        }
+        AnchorKind::Constraint | AnchorKind::Vocabulary => {
+            if anchor.items.iter().all(|item| item.trim().is_empty()) {
+                errors.push(field_error("items", i, "Add at least one entry"));
+            }
+        }
+    }
+    if !is_hex_color(&anchor.color) {
+        errors.push(field_error(
+            "color",
+            i,
+            "Color must be a hex color like #RRGGBB or #RGB",
+        ));
+    }
+}
+
+/// Checks that `name` is set and no other live preset uses it.
+fn validate_preset_name(
+    conn: &Connection,
+    preset_id: Option<i64>,
+    name: &str,
+    errors: &mut Vec<FieldError>,
+) -> Result<(), String> {
+    if name.is_empty() {
+        errors.push(field_error("name", None, "Preset name cannot be empty"));
+    } else {
+        let taken: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE name = ?1 AND deleted_at IS NULL AND id IS NOT ?2)",
+                rusqlite::params![name, preset_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if taken {
+            errors.push(field_error(
+                "name",
+                None,
+                format!("A preset named \"{}\" already exists", name),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_preset(
+    conn: &Connection,
+    preset_id: Option<i64>,
+    name: &str,
+    anchors: &[NewPresetAnchor],
+    errors: &mut Vec<FieldError>,
+) -> Result<(), String> {
+    validate_preset_name(conn, preset_id, name, errors)?;
+
+    if anchors.is_empty() {
+        errors.push(field_error("anchors", None, "A preset needs at least one anchor"));
+    }
+
+    let mut labels = HashSet::new();
+    for (i, anchor) in anchors.iter().enumerate() {
+        validate_anchor_content(anchor, Some(i), errors);
+        let label = anchor.label.trim();
+        if !label.is_empty() && !labels.insert(label) {
+            errors.push(field_error(
+                "label",
+                Some(i),
+                format!("Another anchor is already labelled \"{}\"", label),
+            ));
+        }
+        for (field, value) in [("position_x", anchor.position_x), ("position_y", anchor.position_y)] {
+            if !(0.0..=MAX_CANVAS_COORDINATE).contains(&value) {
+                errors.push(field_error(
+                    field,
+                    Some(i),
+                    format!("Position must be between 0 and {}", MAX_CANVAS_COORDINATE),
+                ));
+            }
+        }
+        if !(anchor.influence_radius > 0.0 && anchor.influence_radius <= MAX_CANVAS_COORDINATE) {
+            errors.push(field_error(
+                "influence_radius",
+                Some(i),
+                format!(
+                    "Influence radius must be greater than 0 and at most {}",
+                    MAX_CANVAS_COORDINATE
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves and validates a preset before it is written, failing with every
+/// field error found.
+fn check_preset(
+    conn: &Connection,
+    preset_id: Option<i64>,
+    name: &str,
+    anchors: Vec<NewPresetAnchor>,
+) -> Result<Vec<NewPresetAnchor>, PresetWriteError> {
+    let mut field_errors = Vec::new();
+    let anchors = resolve_library_anchors(conn, anchors, &mut field_errors);
+    validate_preset(conn, preset_id, name, &anchors, &mut field_errors)?;
+
+    if !field_errors.is_empty() {
+        return Err(PresetWriteError {
+            message: "The preset has invalid fields".to_string(),
+            field_errors,
+        });
+    }
+
+    Ok(anchors)
+}
+
+/// Validates a library anchor's content before it is written.
+pub(crate) fn check_library_anchor(anchor: &LibraryAnchor) -> Result<(), PresetWriteError> {
+    let content = NewPresetAnchor {
+        anchor_id: None,
+        label: anchor.label.clone(),
+        prompt: anchor.prompt.clone(),
+        icon_small: anchor.icon_small.clone(),
+        icon_large: anchor.icon_large.clone(),
+        color: anchor.color.clone(),
+        position_x: 0.0,
+        position_y: 0.0,
+        influence_radius: 0.0,
+        sort_order: 0,
+        kind: anchor.kind,
+        examples: anchor.examples.clone(),
+        items: anchor.items.clone(),
+    };
+    let mut field_errors = Vec::new();
+    validate_anchor_content(&content, None, &mut field_errors);
+
+    if !field_errors.is_empty() {
+        return Err(PresetWriteError {
+            message: "The anchor has invalid fields".to_string(),
+            field_errors,
+        });
+    }
+
+    Ok(())
+}
+
+/// Re-validates every live preset placing library anchor `anchor_id`, as
+/// stored now, failing on the first one a library edit has made invalid.
+pub(crate) fn check_presets_using_anchor(
+    conn: &Connection,
+    anchor_id: i64,
+) -> Result<(), PresetWriteError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name FROM anchor_presets
+             WHERE deleted_at IS NULL
+               AND id IN (SELECT preset_id FROM preset_anchors WHERE anchor_id = ?1)
+             ORDER BY name ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let presets = stmt
+        .query_map(rusqlite::params![anchor_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for (preset_id, name) in presets {
+        let anchors: Vec<NewPresetAnchor> = load_preset_anchors(conn, preset_id)?
+            .into_iter()
+            .map(NewPresetAnchor::from)
+            .collect();
+        let mut field_errors = Vec::new();
+        validate_preset(conn, Some(preset_id), &name, &anchors, &mut field_errors)?;
+
+        if !field_errors.is_empty() {
+            return Err(PresetWriteError {
+                message: format!("This change would make preset \"{}\" invalid", name),
+                field_errors,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn insert_preset_anchors(
+    conn: &Connection,
+    preset_id: i64,
+    anchors: &[NewPresetAnchor],
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(
+            "INSERT INTO preset_anchors (preset_id, anchor_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order, kind, examples, items)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        )
+        .map_err(|e| e.to_string())?;
+
+    for anchor in anchors {
+        let examples = list_to_column(&anchor.examples)?;
+        let items = list_to_column(&anchor.items)?;
+        stmt.execute(rusqlite::params![
+            preset_id,
+            anchor.anchor_id,
+            anchor.label.trim(),
+            &anchor.prompt,
+            &anchor.icon_small,
+            &anchor.icon_large,
+            &anchor.color,
+            anchor.position_x,
+            anchor.position_y,
+            anchor.influence_radius,
+            anchor.sort_order,
+            anchor.kind.as_str(),
+            &examples,
+            &items,
+        ])
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Maps `id, name, builtin_key IS NOT NULL, created_at, updated_at`.
+pub(crate) fn row_to_preset(row: &rusqlite::Row) -> rusqlite::Result<AnchorPreset> {
+    Ok(AnchorPreset {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        is_builtin: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
 
 #[tauri::command]
 pub fn list_presets(db: State<Database>) -> Result<Vec<AnchorPreset>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare("SELECT id, name, created_at, updated_at FROM anchor_presets ORDER BY name ASC")
+        .prepare(
+            "SELECT id, name, builtin_key IS NOT NULL, created_at, updated_at FROM anchor_presets WHERE deleted_at IS NULL ORDER BY name ASC",
+        )
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
-        .query_map([], |row| {
-            Ok(AnchorPreset {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
-            })
-        })
+        .query_map([], row_to_preset)
         .map_err(|e| e.to_string())?;
 
     let mut presets = Vec::new();
@@ -35,93 +374,96 @@ pub fn create_preset(
     db: State<Database>,
     name: String,
     anchors: Vec<NewPresetAnchor>,
-) -> Result<AnchorPreset, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+) -> Result<AnchorPreset, PresetWriteError> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    save_new_preset(&mut conn, name, anchors)
+}
+
+/// Validates and inserts a user preset with its anchors in one transaction.
+pub(crate) fn save_new_preset(
+    conn: &mut Connection,
+    name: String,
+    anchors: Vec<NewPresetAnchor>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let preset = insert_checked_preset(&tx, name.trim(), anchors)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+/// Validates and inserts a user preset with its anchors on `conn`, which the
+/// caller wraps in a transaction.
+fn insert_checked_preset(
+    conn: &Connection,
+    name: &str,
+    anchors: Vec<NewPresetAnchor>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let anchors = check_preset(conn, None, name, anchors)?;
     let timestamp = models::now();
 
     conn.execute(
         "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
-        rusqlite::params![&name, &timestamp, &timestamp],
+        rusqlite::params![name, &timestamp, &timestamp],
     )
     .map_err(|e| e.to_string())?;
 
     let preset_id = conn.last_insert_rowid();
 
-    let mut stmt = conn
-        .prepare(
-            "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        )
-        .map_err(|e| e.to_string())?;
-
-    for anchor in &anchors {
-        stmt.execute(rusqlite::params![
-            preset_id,
-            &anchor.label,
-            &anchor.prompt,
-            &anchor.icon_small,
-            &anchor.icon_large,
-            &anchor.color,
-            anchor.position_x,
-            anchor.position_y,
-            anchor.influence_radius,
-            anchor.sort_order,
-        ])
-        .map_err(|e| e.to_string())?;
-    }
+    insert_preset_anchors(conn, preset_id, &anchors)?;
 
     Ok(AnchorPreset {
         id: preset_id,
-        name,
+        name: name.to_string(),
+        is_builtin: false,
         created_at: timestamp.clone(),
         updated_at: timestamp,
     })
 }
 
+/// Places `anchors` evenly on a circle, in order, as the canvas does for a
+/// preset that was never laid out.
+pub(crate) fn lay_out_in_circle(anchors: &mut [NewPresetAnchor]) {
+    let positions = weights::circle_layout(anchors.len());
+    for (anchor, (x, y)) in anchors.iter_mut().zip(positions) {
+        anchor.position_x = x;
+        anchor.position_y = y;
+    }
+}
+
 #[tauri::command]
 pub fn update_preset(
     db: State<Database>,
     preset_id: i64,
     name: String,
     anchors: Vec<NewPresetAnchor>,
-) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+) -> Result<(), PresetWriteError> {
+    let name = name.trim().to_string();
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let anchors = check_preset(&tx, Some(preset_id), &name, anchors)?;
     let timestamp = models::now();
 
-    conn.execute(
-        "UPDATE anchor_presets SET name = ?1, updated_at = ?2 WHERE id = ?3",
-        rusqlite::params![&name, &timestamp, preset_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let updated = tx
+        .execute(
+            "UPDATE anchor_presets SET name = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![&name, &timestamp, preset_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Preset {} not found", preset_id).into());
+    }
 
-    conn.execute(
+    tx.execute(
         "DELETE FROM preset_anchors WHERE preset_id = ?1",
         rusqlite::params![preset_id],
     )
     .map_err(|e| e.to_string())?;
 
-    let mut stmt = conn
-        .prepare(
-            "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        )
-        .map_err(|e| e.to_string())?;
+    insert_preset_anchors(&tx, preset_id, &anchors)?;
 
-    for anchor in &anchors {
-        stmt.execute(rusqlite::params![
-            preset_id,
-            &anchor.label,
-            &anchor.prompt,
-            &anchor.icon_small,
-            &anchor.icon_large,
-            &anchor.color,
-            anchor.position_x,
-            anchor.position_y,
-            anchor.influence_radius,
-            anchor.sort_order,
-        ])
-        .map_err(|e| e.to_string())?;
-    }
+    tx.commit().map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -131,46 +473,282 @@ pub fn rename_preset(
     db: State<Database>,
     preset_id: i64,
     name: String,
-) -> Result<(), String> {
+) -> Result<(), PresetWriteError> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let timestamp = models::now();
+    rename_live_preset(&conn, preset_id, name.trim())
+}
+
+fn rename_live_preset(conn: &Connection, preset_id: i64, name: &str) -> Result<(), PresetWriteError> {
+    let mut field_errors = Vec::new();
+    validate_preset_name(conn, Some(preset_id), name, &mut field_errors)?;
+    if !field_errors.is_empty() {
+        return Err(PresetWriteError {
+            message: "The preset has invalid fields".to_string(),
+            field_errors,
+        });
+    }
+
+    let updated = conn
+        .execute(
+            "UPDATE anchor_presets SET name = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![name, models::now(), preset_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Preset {} not found", preset_id).into());
+    }
+
+    Ok(())
+}
+
+/// The `builtin_key` of a live preset, `None` for user presets.
+fn preset_builtin_key(conn: &Connection, preset_id: i64) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT builtin_key FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL",
+        rusqlite::params![preset_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Preset {} not found", preset_id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn delete_preset(db: State<Database>, preset_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    if preset_builtin_key(&conn, preset_id)?.is_some() {
+        return Err("Built-in presets cannot be deleted".to_string());
+    }
 
     conn.execute(
-        "UPDATE anchor_presets SET name = ?1, updated_at = ?2 WHERE id = ?3",
-        rusqlite::params![&name, &timestamp, preset_id],
+        "UPDATE anchor_presets SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        rusqlite::params![models::now(), preset_id],
     )
     .map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
+/// Replaces a built-in preset's anchors with the ones it shipped with. Its
+/// name is left as is.
 #[tauri::command]
-pub fn delete_preset(db: State<Database>, preset_id: i64) -> Result<(), String> {
-    if preset_id == 1 {
-        return Err("Cannot delete the default preset".to_string());
-    }
+pub fn reset_builtin_preset(db: State<Database>, preset_id: i64) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let builtin = preset_builtin_key(&tx, preset_id)?
+        .and_then(|key| builtin_presets::find(&key))
+        .ok_or_else(|| format!("Preset {} is not a built-in preset", preset_id))?;
 
-    conn.execute(
-        "DELETE FROM anchor_presets WHERE id = ?1",
+    tx.execute(
+        "DELETE FROM preset_anchors WHERE preset_id = ?1",
         rusqlite::params![preset_id],
     )
     .map_err(|e| e.to_string())?;
 
+    builtin_presets::insert_anchors(&tx, preset_id, builtin).map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "UPDATE anchor_presets SET updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![models::now(), preset_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
+/// `base`, or the first of "<base> (2)", "<base> (3)", ... that no live
+/// preset uses.
+pub(crate) fn unique_preset_name(conn: &Connection, base: &str) -> Result<String, String> {
+    let mut name = base.to_string();
+    for n in 2.. {
+        let taken: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE name = ?1 AND deleted_at IS NULL)",
+                rusqlite::params![&name],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !taken {
+            break;
+        }
+        name = format!("{} ({})", base, n);
+    }
+
+    Ok(name)
+}
+
+/// Copies a live preset, its anchors and its defaults into a new user preset
+/// named `new_name` or "<name> (copy)". Anchors keep their positions unless
+/// the source was never laid out, in which case the copy is.
+fn copy_preset(
+    conn: &Connection,
+    preset_id: i64,
+    new_name: Option<String>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let source_name: String = conn
+        .query_row(
+            "SELECT name FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![preset_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Preset {} not found", preset_id),
+            e => e.to_string(),
+        })?;
+
+    let name = new_name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", source_name));
+
+    let mut anchors: Vec<NewPresetAnchor> = load_preset_anchors(conn, preset_id)?
+        .into_iter()
+        .map(NewPresetAnchor::from)
+        .collect();
+    if weights::is_unplaced(anchors.iter().map(|a| (a.position_x, a.position_y))) {
+        lay_out_in_circle(&mut anchors);
+    }
+
+    let preset = insert_checked_preset(conn, &name, anchors)?;
+
+    conn.execute(
+        "UPDATE anchor_presets SET defaults = (SELECT defaults FROM anchor_presets WHERE id = ?2)
+         WHERE id = ?1",
+        rusqlite::params![preset.id, preset_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+/// Copies a built-in preset, as it is now, into a new user preset that can
+/// be freely edited and deleted.
 #[tauri::command]
-pub fn get_preset_anchors(
+pub fn clone_builtin_preset(
     db: State<Database>,
     preset_id: i64,
-) -> Result<Vec<PresetAnchor>, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    new_name: Option<String>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if preset_builtin_key(&tx, preset_id)?.is_none() {
+        return Err(format!("Preset {} is not a built-in preset", preset_id).into());
+    }
+
+    let preset = copy_preset(&tx, preset_id, new_name)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+#[tauri::command]
+pub fn duplicate_preset(
+    db: State<Database>,
+    preset_id: i64,
+    new_name: String,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let preset = copy_preset(&tx, preset_id, Some(new_name))?;
+    tx.commit().map_err(|e| e.to_string())?;
 
+    Ok(preset)
+}
+
+/// Builds a new preset from the anchors of several presets, taken in the
+/// order given. Anchors sharing a label are resolved by `conflict_policy`,
+/// and the result is laid out afresh in a circle.
+#[tauri::command]
+pub fn merge_presets(
+    db: State<Database>,
+    preset_ids: Vec<i64>,
+    new_name: String,
+    conflict_policy: Option<MergeConflictPolicy>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let preset = merge_into_new_preset(&tx, preset_ids, new_name.trim(), conflict_policy)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(preset)
+}
+
+fn merge_into_new_preset(
+    conn: &Connection,
+    preset_ids: Vec<i64>,
+    name: &str,
+    conflict_policy: Option<MergeConflictPolicy>,
+) -> Result<AnchorPreset, PresetWriteError> {
+    let mut unique_ids = Vec::new();
+    for id in preset_ids {
+        if !unique_ids.contains(&id) {
+            unique_ids.push(id);
+        }
+    }
+    if unique_ids.len() < 2 {
+        return Err("Pick at least two presets to merge".to_string().into());
+    }
+    let conflict_policy = conflict_policy.unwrap_or_default();
+
+    let mut merged: Vec<PresetAnchor> = Vec::new();
+    for &preset_id in &unique_ids {
+        let live: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL)",
+                rusqlite::params![preset_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !live {
+            return Err(format!("Preset {} not found", preset_id).into());
+        }
+
+        for mut anchor in load_preset_anchors(conn, preset_id)? {
+            let existing = merged.iter().position(|m| m.label == anchor.label);
+            match (existing, conflict_policy) {
+                (None, _) => merged.push(anchor),
+                (Some(_), MergeConflictPolicy::First) => {}
+                (Some(i), MergeConflictPolicy::Last) => merged[i] = anchor,
+                (Some(_), MergeConflictPolicy::Rename) => {
+                    // A renamed copy would be overwritten by library edits
+                    anchor.anchor_id = None;
+                    let base = anchor.label.clone();
+                    let mut n = 2;
+                    while merged.iter().any(|m| m.label == anchor.label) {
+                        anchor.label = format!("{} ({})", base, n);
+                        n += 1;
+                    }
+                    merged.push(anchor);
+                }
+            }
+        }
+    }
+
+    let mut anchors: Vec<NewPresetAnchor> = merged
+        .into_iter()
+        .enumerate()
+        .map(|(i, a)| NewPresetAnchor {
+            sort_order: i as i32,
+            ..NewPresetAnchor::from(a)
+        })
+        .collect();
+    lay_out_in_circle(&mut anchors);
+
+    insert_checked_preset(conn, name, anchors)
+}
+
+fn load_preset_anchors(conn: &Connection, preset_id: i64) -> Result<Vec<PresetAnchor>, String> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order
+            "SELECT id, preset_id, anchor_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order, kind, examples, items
              FROM preset_anchors
              WHERE preset_id = ?1
              ORDER BY sort_order ASC",
@@ -182,15 +760,19 @@ pub fn get_preset_anchors(
             Ok(PresetAnchor {
                 id: row.get(0)?,
                 preset_id: row.get(1)?,
-                label: row.get(2)?,
-                prompt: row.get(3)?,
-                icon_small: row.get(4)?,
-                icon_large: row.get(5)?,
-                color: row.get(6)?,
-                position_x: row.get(7)?,
-                position_y: row.get(8)?,
-                influence_radius: row.get(9)?,
-                sort_order: row.get(10)?,
+                anchor_id: row.get(2)?,
+                label: row.get(3)?,
+                prompt: row.get(4)?,
+                icon_small: row.get(5)?,
+                icon_large: row.get(6)?,
+                color: row.get(7)?,
+                position_x: row.get(8)?,
+                position_y: row.get(9)?,
+                influence_radius: row.get(10)?,
+                sort_order: row.get(11)?,
+                kind: kind_from_column(row.get(12)?),
+                examples: list_from_column(row.get(13)?),
+                items: list_from_column(row.get(14)?),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -202,3 +784,366 @@ pub fn get_preset_anchors(
 
     Ok(anchors)
 }
+
+#[tauri::command]
+pub fn get_preset_anchors(
+    db: State<Database>,
+    preset_id: i64,
+) -> Result<Vec<PresetAnchor>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_preset_anchors(&conn, preset_id)
+}
+
+/// Generation defaults of a live preset; `None` when it has none.
+pub(crate) fn load_preset_defaults(
+    conn: &Connection,
+    preset_id: i64,
+) -> Result<Option<PresetDefaults>, String> {
+    let defaults: Option<String> = conn
+        .query_row(
+            "SELECT defaults FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL",
+            rusqlite::params![preset_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Preset {} not found", preset_id),
+            e => e.to_string(),
+        })?;
+
+    Ok(defaults.and_then(|d| serde_json::from_str(&d).ok()))
+}
+
+#[tauri::command]
+pub fn get_preset_defaults(
+    db: State<Database>,
+    preset_id: i64,
+) -> Result<Option<PresetDefaults>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_preset_defaults(&conn, preset_id)
+}
+
+/// Sets the generation settings applied while this preset is active, or
+/// clears them with `None`. Built-in presets accept defaults too, and
+/// resetting one leaves them alone.
+#[tauri::command]
+pub fn set_preset_defaults(
+    db: State<Database>,
+    preset_id: i64,
+    defaults: Option<PresetDefaults>,
+) -> Result<(), String> {
+    // Defaults with nothing set are stored as none at all
+    let defaults = defaults.filter(|d| *d != PresetDefaults::default());
+    if let Some(ref d) = defaults {
+        d.validate()?;
+    }
+    let defaults = defaults
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let updated = conn
+        .execute(
+            "UPDATE anchor_presets SET defaults = ?1, updated_at = ?2
+             WHERE id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![defaults, models::now(), preset_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Preset {} not found", preset_id));
+    }
+
+    Ok(())
+}
+
+/// Anchor weights, keyed by label, for a handle at (`handle_x`, `handle_y`)
+/// on a preset's canvas. Lets callers without a canvas drive the mixer.
+#[tauri::command]
+pub fn compute_weights(
+    db: State<Database>,
+    preset_id: i64,
+    handle_x: f64,
+    handle_y: f64,
+    falloff: Option<Falloff>,
+    normalization: Option<Normalization>,
+) -> Result<BTreeMap<String, f64>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    preset_weights(
+        &conn,
+        preset_id,
+        handle_x,
+        handle_y,
+        falloff.unwrap_or_default(),
+        normalization.unwrap_or_default(),
+    )
+}
+
+pub(crate) fn preset_weights(
+    conn: &Connection,
+    preset_id: i64,
+    handle_x: f64,
+    handle_y: f64,
+    falloff: Falloff,
+    normalization: Normalization,
+) -> Result<BTreeMap<String, f64>, String> {
+    if !handle_x.is_finite() || !handle_y.is_finite() {
+        return Err("Handle position must be finite".to_string());
+    }
+
+    let live: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL)",
+            rusqlite::params![preset_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !live {
+        return Err(format!("Preset {} not found", preset_id));
+    }
+
+    let anchors = load_preset_anchors(conn, preset_id)?;
+    // Unplaced anchors all sit at the origin until the canvas lays them out
+    if weights::is_unplaced(anchors.iter().map(|a| (a.position_x, a.position_y))) {
+        return Err(format!(
+            "Preset {} has not been laid out on the canvas yet",
+            preset_id
+        ));
+    }
+
+    Ok(weights::compute_weights(
+        &anchors,
+        handle_x,
+        handle_y,
+        falloff,
+        normalization,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(label: &str, x: f64, y: f64) -> NewPresetAnchor {
+        NewPresetAnchor {
+            anchor_id: None,
+            label: label.to_string(),
+            prompt: format!("Be {}", label),
+            icon_small: "star".to_string(),
+            icon_large: "star".to_string(),
+            color: "#336699".to_string(),
+            position_x: x,
+            position_y: y,
+            influence_radius: 200.0,
+            sort_order: 0,
+            kind: AnchorKind::Persona,
+            examples: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    fn library_anchor(conn: &Connection, label: &str) -> i64 {
+        let now = models::now();
+        conn.execute(
+            "INSERT INTO anchors (label, prompt, icon_small, icon_large, color, kind, created_at, updated_at)
+             VALUES (?1, 'Be it', 'star', 'star', '#336699', 'persona', ?2, ?2)",
+            rusqlite::params![label, now],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    #[test]
+    fn a_library_rename_that_duplicates_a_preset_label_is_rejected() {
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let anchor_id = library_anchor(&conn, "Calm");
+        let linked = NewPresetAnchor {
+            anchor_id: Some(anchor_id),
+            ..anchor("Calm", 100.0, 100.0)
+        };
+        save_new_preset(&mut conn, "Moods".to_string(), vec![linked, anchor("Bold", 300.0, 300.0)])
+            .unwrap();
+        assert!(check_presets_using_anchor(&conn, anchor_id).is_ok());
+
+        conn.execute(
+            "UPDATE preset_anchors SET label = 'Bold' WHERE anchor_id = ?1",
+            rusqlite::params![anchor_id],
+        )
+        .unwrap();
+        let error = check_presets_using_anchor(&conn, anchor_id).unwrap_err();
+        assert!(error.message.contains("Moods"));
+        assert_eq!(error.field_errors[0].field, "label");
+    }
+
+    #[test]
+    fn library_anchors_need_valid_content() {
+        let mut library = LibraryAnchor {
+            id: 1,
+            label: "Calm".to_string(),
+            prompt: "Be calm".to_string(),
+            icon_small: "star".to_string(),
+            icon_large: "star".to_string(),
+            color: "#336699".to_string(),
+            kind: AnchorKind::Persona,
+            examples: Vec::new(),
+            items: Vec::new(),
+            created_at: models::now(),
+            updated_at: models::now(),
+        };
+        assert!(check_library_anchor(&library).is_ok());
+
+        library.color = "blue".to_string();
+        library.prompt = " ".to_string();
+        let fields: Vec<String> = check_library_anchor(&library)
+            .unwrap_err()
+            .field_errors
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert_eq!(fields, ["prompt", "color"]);
+    }
+
+    fn builtin_preset_id(conn: &Connection) -> i64 {
+        conn.query_row(
+            "SELECT id FROM anchor_presets WHERE builtin_key = ?1",
+            rusqlite::params![builtin_presets::EMOTIONS_KEY],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_copy_of_an_unplaced_preset_is_laid_out_and_weighable() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let source = builtin_preset_id(&conn);
+        conn.execute(
+            "UPDATE preset_anchors SET position_x = 0, position_y = 0 WHERE preset_id = ?1",
+            rusqlite::params![source],
+        )
+        .unwrap();
+
+        let copy = copy_preset(&conn, source, None).unwrap();
+        let anchors = load_preset_anchors(&conn, copy.id).unwrap();
+        let expected = weights::circle_layout(anchors.len());
+        for (anchor, (x, y)) in anchors.iter().zip(expected) {
+            assert_eq!((anchor.position_x, anchor.position_y), (x, y));
+        }
+
+        let (x, y) = (anchors[0].position_x, anchors[0].position_y);
+        let weights =
+            preset_weights(&conn, copy.id, x, y, Falloff::Linear, Normalization::None).unwrap();
+        assert_eq!(weights[&anchors[0].label], 1.0);
+    }
+
+    #[test]
+    fn a_copy_keeps_placed_positions() {
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let source = save_new_preset(
+            &mut conn,
+            "Moods".to_string(),
+            vec![anchor("Calm", 120.0, 80.0), anchor("Bold", 400.0, 300.0)],
+        )
+        .unwrap();
+
+        let copy = copy_preset(&conn, source.id, None).unwrap();
+        assert_eq!(copy.name, "Moods (copy)");
+        let positions: Vec<(f64, f64)> = load_preset_anchors(&conn, copy.id)
+            .unwrap()
+            .iter()
+            .map(|a| (a.position_x, a.position_y))
+            .collect();
+        assert_eq!(positions, [(120.0, 80.0), (400.0, 300.0)]);
+    }
+
+    #[test]
+    fn merging_validates_and_lays_out_the_result() {
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let a = save_new_preset(&mut conn, "A".to_string(), vec![anchor("Calm", 10.0, 10.0)]).unwrap();
+        let b = save_new_preset(&mut conn, "B".to_string(), vec![anchor("Calm", 20.0, 20.0)]).unwrap();
+
+        let error = merge_into_new_preset(&conn, vec![a.id, b.id], "A", None).unwrap_err();
+        assert_eq!(error.field_errors[0].field, "name");
+
+        let merged = merge_into_new_preset(
+            &conn,
+            vec![a.id, b.id],
+            "AB",
+            Some(MergeConflictPolicy::Rename),
+        )
+        .unwrap();
+        let anchors = load_preset_anchors(&conn, merged.id).unwrap();
+        let labels: Vec<&str> = anchors.iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, ["Calm", "Calm (2)"]);
+        assert!(!weights::is_unplaced(anchors.iter().map(|a| (a.position_x, a.position_y))));
+    }
+
+    #[test]
+    fn renaming_checks_the_name_and_skips_trashed_presets() {
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let a = save_new_preset(&mut conn, "A".to_string(), vec![anchor("Calm", 10.0, 10.0)]).unwrap();
+        save_new_preset(&mut conn, "B".to_string(), vec![anchor("Calm", 10.0, 10.0)]).unwrap();
+
+        assert_eq!(rename_live_preset(&conn, a.id, "").unwrap_err().field_errors[0].field, "name");
+        assert_eq!(rename_live_preset(&conn, a.id, "B").unwrap_err().field_errors[0].field, "name");
+        rename_live_preset(&conn, a.id, "A2").unwrap();
+
+        conn.execute(
+            "UPDATE anchor_presets SET deleted_at = ?1 WHERE id = ?2",
+            rusqlite::params![models::now(), a.id],
+        )
+        .unwrap();
+        let error = rename_live_preset(&conn, a.id, "A3").unwrap_err();
+        assert!(error.field_errors.is_empty());
+        assert!(error.message.contains("not found"));
+    }
+
+    #[test]
+    fn a_library_update_leaves_trashed_presets_alone() {
+        let db = crate::db::open_in_memory();
+        let mut conn = db.0.lock().unwrap();
+        let anchor_id = library_anchor(&conn, "Calm");
+        let linked = || NewPresetAnchor {
+            anchor_id: Some(anchor_id),
+            ..anchor("Calm", 100.0, 100.0)
+        };
+        let live = save_new_preset(&mut conn, "Moods".to_string(), vec![linked()]).unwrap();
+        let trashed = save_new_preset(&mut conn, "Old moods".to_string(), vec![linked()]).unwrap();
+        conn.execute(
+            "UPDATE anchor_presets SET deleted_at = ?1 WHERE id = ?2",
+            rusqlite::params![models::now(), trashed.id],
+        )
+        .unwrap();
+
+        let library = LibraryAnchor {
+            id: anchor_id,
+            label: "Serene".to_string(),
+            prompt: "Be serene".to_string(),
+            icon_small: "star".to_string(),
+            icon_large: "star".to_string(),
+            color: "#336699".to_string(),
+            kind: AnchorKind::Persona,
+            examples: Vec::new(),
+            items: Vec::new(),
+            created_at: models::now(),
+            updated_at: models::now(),
+        };
+        crate::commands::anchors::write_library_anchor(&conn, &library).unwrap();
+
+        let label = |preset_id: i64| -> String {
+            conn.query_row(
+                "SELECT label FROM preset_anchors WHERE preset_id = ?1",
+                rusqlite::params![preset_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(label(live.id), "Serene");
+        assert_eq!(label(trashed.id), "Calm");
+    }
+}
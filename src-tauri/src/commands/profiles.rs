@@ -0,0 +1,195 @@
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::{self, ModelProfile, NewModelProfile, ReasoningDelimiter};
+use crate::reasoning;
+
+const PROFILE_COLUMNS: &str = "id, name, model, context_window, reasoning_delimiters, reasoning_field, \
+     generation_params, created_at, updated_at";
+
+fn row_to_profile(row: &rusqlite::Row) -> rusqlite::Result<ModelProfile> {
+    // NULL (or unreadable) delimiters fall back to <think>...</think>
+    let delimiters: Option<String> = row.get(4)?;
+    let reasoning_delimiters = delimiters
+        .and_then(|d| serde_json::from_str(&d).ok())
+        .unwrap_or_else(reasoning::default_delimiters);
+    let generation: Option<String> = row.get(6)?;
+    let generation = generation
+        .and_then(|g| serde_json::from_str(&g).ok())
+        .unwrap_or_default();
+
+    Ok(ModelProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        model: row.get(2)?,
+        context_window: row.get(3)?,
+        reasoning_delimiters,
+        reasoning_field: row.get(5)?,
+        generation,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn validate_delimiters(delimiters: &[ReasoningDelimiter]) -> Result<(), String> {
+    if delimiters.iter().any(|d| d.start.is_empty() || d.end.is_empty()) {
+        return Err("Reasoning delimiters need both a start and an end tag".to_string());
+    }
+    Ok(())
+}
+
+/// Picks the profile for a session: the explicit `profile_id` if given,
+/// otherwise the most recently updated profile whose model matches.
+pub(crate) fn resolve_profile(
+    conn: &Connection,
+    profile_id: Option<i64>,
+    model: &str,
+) -> Result<Option<ModelProfile>, String> {
+    let result = match profile_id {
+        Some(id) => conn.query_row(
+            &format!("SELECT {} FROM model_profiles WHERE id = ?1", PROFILE_COLUMNS),
+            rusqlite::params![id],
+            row_to_profile,
+        ),
+        None => conn.query_row(
+            &format!(
+                "SELECT {} FROM model_profiles WHERE model = ?1 ORDER BY updated_at DESC LIMIT 1",
+                PROFILE_COLUMNS
+            ),
+            rusqlite::params![model],
+            row_to_profile,
+        ),
+    };
+
+    match result {
+        Ok(profile) => Ok(Some(profile)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => match profile_id {
+            Some(id) => Err(format!("Profile {} not found", id)),
+            None => Ok(None),
+        },
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles(db: State<Database>) -> Result<Vec<ModelProfile>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM model_profiles ORDER BY name ASC",
+            PROFILE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], row_to_profile)
+        .map_err(|e| e.to_string())?;
+
+    let mut profiles = Vec::new();
+    for row in rows {
+        profiles.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(profiles)
+}
+
+/// Validates a profile and serializes its JSON columns.
+fn profile_json(profile: &NewModelProfile) -> Result<(String, String), String> {
+    if profile.context_window <= 0 {
+        return Err("Context window must be positive".to_string());
+    }
+    validate_delimiters(&profile.reasoning_delimiters)?;
+    profile.generation.validate()?;
+
+    let delimiters_json =
+        serde_json::to_string(&profile.reasoning_delimiters).map_err(|e| e.to_string())?;
+    let generation_json = serde_json::to_string(&profile.generation).map_err(|e| e.to_string())?;
+
+    Ok((delimiters_json, generation_json))
+}
+
+#[tauri::command]
+pub fn create_profile(
+    db: State<Database>,
+    profile: NewModelProfile,
+) -> Result<ModelProfile, String> {
+    let (delimiters_json, generation_json) = profile_json(&profile)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let timestamp = models::now();
+
+    conn.execute(
+        "INSERT INTO model_profiles (name, model, context_window, reasoning_delimiters, reasoning_field, generation_params, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            &profile.name,
+            &profile.model,
+            profile.context_window,
+            &delimiters_json,
+            &profile.reasoning_field,
+            &generation_json,
+            &timestamp,
+            &timestamp
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(ModelProfile {
+        id: conn.last_insert_rowid(),
+        name: profile.name,
+        model: profile.model,
+        context_window: profile.context_window,
+        reasoning_delimiters: profile.reasoning_delimiters,
+        reasoning_field: profile.reasoning_field,
+        generation: profile.generation,
+        created_at: timestamp.clone(),
+        updated_at: timestamp,
+    })
+}
+
+#[tauri::command]
+pub fn update_profile(
+    db: State<Database>,
+    profile_id: i64,
+    profile: NewModelProfile,
+) -> Result<(), String> {
+    let (delimiters_json, generation_json) = profile_json(&profile)?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let timestamp = models::now();
+
+    conn.execute(
+        "UPDATE model_profiles
+         SET name = ?1, model = ?2, context_window = ?3, reasoning_delimiters = ?4, reasoning_field = ?5,
+             generation_params = ?6, updated_at = ?7
+         WHERE id = ?8",
+        rusqlite::params![
+            &profile.name,
+            &profile.model,
+            profile.context_window,
+            &delimiters_json,
+            &profile.reasoning_field,
+            &generation_json,
+            &timestamp,
+            profile_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_profile(db: State<Database>, profile_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM model_profiles WHERE id = ?1",
+        rusqlite::params![profile_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
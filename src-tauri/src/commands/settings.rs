@@ -1,15 +1,20 @@
+use rusqlite::Connection;
 use serde::Deserialize;
 use tauri::State;
 
 use crate::db::Database;
+use crate::models::{self, ModelPrice};
 
 #[tauri::command]
 pub fn get_setting(db: State<Database>, key: String) -> Result<Option<String>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_setting(&conn, &key)
+}
 
+pub(crate) fn load_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
     let result = conn.query_row(
         "SELECT value FROM app_state WHERE key = ?1",
-        rusqlite::params![&key],
+        rusqlite::params![key],
         |row| row.get::<_, String>(0),
     );
 
@@ -33,6 +38,70 @@ pub fn set_setting(db: State<Database>, key: String, value: String) -> Result<()
     Ok(())
 }
 
+#[tauri::command]
+pub fn list_model_prices(db: State<Database>) -> Result<Vec<ModelPrice>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, prompt_price, completion_price, updated_at FROM model_prices ORDER BY model ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ModelPrice {
+                model: row.get(0)?,
+                prompt_price: row.get(1)?,
+                completion_price: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut prices = Vec::new();
+    for row in rows {
+        prices.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(prices)
+}
+
+#[tauri::command]
+pub fn set_model_price(
+    db: State<Database>,
+    model: String,
+    prompt_price: f64,
+    completion_price: f64,
+) -> Result<(), String> {
+    if prompt_price < 0.0 || completion_price < 0.0 {
+        return Err("Prices cannot be negative".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO model_prices (model, prompt_price, completion_price, updated_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![&model, prompt_price, completion_price, models::now()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_model_price(db: State<Database>, model: String) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM model_prices WHERE model = ?1",
+        rusqlite::params![&model],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
 #[derive(Debug, Deserialize)]
 struct ModelsResponse {
     data: Vec<ModelEntry>,
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::{self, Tag};
+
+pub(crate) fn load_chat_tags(conn: &Connection, chat_id: i64) -> Result<Vec<Tag>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color
+             FROM chat_tags ct
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.chat_id = ?1
+             ORDER BY t.name ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![chat_id], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(tags)
+}
+
+/// Tags of every chat, keyed by chat id, for building the chat list.
+pub(crate) fn load_tags_by_chat(conn: &Connection) -> Result<HashMap<i64, Vec<Tag>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT ct.chat_id, t.id, t.name, t.color
+             FROM chat_tags ct
+             JOIN tags t ON t.id = ct.tag_id
+             ORDER BY t.name ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                Tag {
+                    id: row.get(1)?,
+                    name: row.get(2)?,
+                    color: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut tags: HashMap<i64, Vec<Tag>> = HashMap::new();
+    for row in rows {
+        let (chat_id, tag) = row.map_err(|e| e.to_string())?;
+        tags.entry(chat_id).or_default().push(tag);
+    }
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub fn list_tags(db: State<Database>) -> Result<Vec<Tag>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, color FROM tags ORDER BY name ASC")
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub fn create_tag(
+    db: State<Database>,
+    name: String,
+    color: Option<String>,
+) -> Result<Tag, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO tags (name, color, created_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![&name, &color, models::now()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(Tag {
+        id: conn.last_insert_rowid(),
+        name,
+        color,
+    })
+}
+
+#[tauri::command]
+pub fn update_tag(
+    db: State<Database>,
+    tag_id: i64,
+    name: String,
+    color: Option<String>,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+        rusqlite::params![&name, &color, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_tag(db: State<Database>, tag_id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM tags WHERE id = ?1", rusqlite::params![tag_id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Replaces the tags on a chat with `tag_ids`.
+#[tauri::command]
+pub fn set_chat_tags(db: State<Database>, chat_id: i64, tag_ids: Vec<i64>) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "DELETE FROM chat_tags WHERE chat_id = ?1",
+        rusqlite::params![chat_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    for tag_id in tag_ids {
+        tx.execute(
+            "INSERT OR IGNORE INTO chat_tags (chat_id, tag_id) VALUES (?1, ?2)",
+            rusqlite::params![chat_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use rusqlite::Connection;
+use tauri::State;
+
+use crate::commands::presets::unique_preset_name;
+use crate::db::{self, Database};
+use crate::models::TrashItem;
+
+fn trash_table(kind: &str) -> Result<&'static str, String> {
+    match kind {
+        "chat" => Ok("chats"),
+        "message" => Ok("messages"),
+        "preset" => Ok("anchor_presets"),
+        _ => Err(format!(
+            "Unknown trash item kind: {}. Use 'chat', 'message' or 'preset'.",
+            kind
+        )),
+    }
+}
+
+/// Lists trashed items, newest first. Messages of a trashed chat are not
+/// listed separately; restoring the chat brings them back.
+#[tauri::command]
+pub fn list_trash(db: State<Database>) -> Result<Vec<TrashItem>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT 'chat', id, NULL, title, deleted_at
+             FROM chats
+             WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'message', m.id, m.chat_id, substr(m.content, 1, 100), m.deleted_at
+             FROM messages m
+             JOIN chats c ON c.id = m.chat_id
+             WHERE m.deleted_at IS NOT NULL AND c.deleted_at IS NULL
+             UNION ALL
+             SELECT 'preset', id, NULL, name, deleted_at
+             FROM anchor_presets
+             WHERE deleted_at IS NOT NULL
+             ORDER BY 5 DESC, 2 ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(TrashItem {
+                kind: row.get(0)?,
+                id: row.get(1)?,
+                chat_id: row.get(2)?,
+                title: row.get(3)?,
+                deleted_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut items = Vec::new();
+    for row in rows {
+        items.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(items)
+}
+
+/// Restores a trashed item. Restoring a message also restores the other
+/// messages removed from its chat by the same delete.
+#[tauri::command]
+pub fn restore_trash_item(db: State<Database>, kind: String, id: i64) -> Result<(), String> {
+    let table = trash_table(&kind)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    if restore_row(&conn, table, id)? == 0 {
+        return Err(format!("{} {} is not in the trash", kind, id));
+    }
+
+    Ok(())
+}
+
+/// Clears `deleted_at` on one trashed row and returns how many rows came back.
+/// A preset whose name a live preset has taken meanwhile comes back renamed.
+fn restore_row(conn: &Connection, table: &str, id: i64) -> Result<usize, String> {
+    match table {
+        "messages" => conn.execute(
+            "UPDATE messages SET deleted_at = NULL
+             WHERE (chat_id, deleted_at) = (SELECT chat_id, deleted_at FROM messages WHERE id = ?1)",
+            rusqlite::params![id],
+        ),
+        "anchor_presets" => {
+            let name: Option<String> = match conn.query_row(
+                "SELECT name FROM anchor_presets WHERE id = ?1 AND deleted_at IS NOT NULL",
+                rusqlite::params![id],
+                |row| row.get(0),
+            ) {
+                Ok(name) => Some(name),
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(e) => return Err(e.to_string()),
+            };
+            let Some(name) = name else {
+                return Ok(0);
+            };
+            let name = unique_preset_name(conn, &name)?;
+            conn.execute(
+                "UPDATE anchor_presets SET deleted_at = NULL, name = ?1 WHERE id = ?2",
+                rusqlite::params![name, id],
+            )
+        }
+        _ => conn.execute(
+            &format!(
+                "UPDATE {} SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+                table
+            ),
+            rusqlite::params![id],
+        ),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Permanently deletes one trashed item.
+#[tauri::command]
+pub fn purge_trash_item(db: State<Database>, kind: String, id: i64) -> Result<(), String> {
+    let table = trash_table(&kind)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        &format!(
+            "DELETE FROM {} WHERE id = ?1 AND deleted_at IS NOT NULL",
+            table
+        ),
+        rusqlite::params![id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn empty_trash(db: State<Database>) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    for table in ["messages", "chats", "anchor_presets"] {
+        conn.execute(
+            &format!("DELETE FROM {} WHERE deleted_at IS NOT NULL", table),
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_trash_retention_days(db: State<Database>) -> Result<u32, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    db::trash_retention_days(&conn).map_err(|e| e.to_string())
+}
+
+/// Sets how many days trashed items are kept (0 keeps them forever) and
+/// purges anything already past the new limit.
+#[tauri::command]
+pub fn set_trash_retention_days(db: State<Database>, days: u32) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO app_state (key, value) VALUES (?1, ?2)",
+        rusqlite::params![db::TRASH_RETENTION_KEY, days.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+
+    db::purge_expired_trash(&conn).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_preset(conn: &Connection, name: &str) -> i64 {
+        let now = crate::models::now();
+        conn.execute(
+            "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES (?1, ?2, ?2)",
+            rusqlite::params![name, now],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn trash_preset(conn: &Connection, id: i64) {
+        conn.execute(
+            "UPDATE anchor_presets SET deleted_at = ?1 WHERE id = ?2",
+            rusqlite::params![crate::models::now(), id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn a_trashed_preset_survives_its_name_being_reused() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+
+        let trashed = insert_preset(&conn, "Moods");
+        trash_preset(&conn, trashed);
+        let live = insert_preset(&conn, "Moods");
+        assert_ne!(trashed, live);
+
+        assert_eq!(restore_row(&conn, "anchor_presets", trashed).unwrap(), 1);
+        let name: String = conn
+            .query_row(
+                "SELECT name FROM anchor_presets WHERE id = ?1 AND deleted_at IS NULL",
+                rusqlite::params![trashed],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(name, "Moods (2)");
+    }
+
+    #[test]
+    fn two_live_presets_cannot_share_a_name() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+
+        insert_preset(&conn, "Moods");
+        let now = crate::models::now();
+        let duplicate = conn.execute(
+            "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES ('Moods', ?1, ?1)",
+            rusqlite::params![now],
+        );
+        assert!(duplicate.is_err());
+    }
+
+    #[test]
+    fn restoring_a_live_preset_does_nothing() {
+        let db = crate::db::open_in_memory();
+        let conn = db.0.lock().unwrap();
+
+        let id = insert_preset(&conn, "Moods");
+        assert_eq!(restore_row(&conn, "anchor_presets", id).unwrap(), 0);
+    }
+}
@@ -0,0 +1,235 @@
+use tauri::State;
+
+use crate::db::Database;
+use crate::models::{self, LlmCall, UsageSummary};
+
+pub(crate) const CALL_KIND_MIX: &str = "mix";
+pub(crate) const CALL_KIND_CONTINUATION: &str = "continuation";
+pub(crate) const CALL_KIND_SUMMARY: &str = "summary";
+pub(crate) const CALL_KIND_TITLE: &str = "title";
+pub(crate) const CALL_KIND_PRESET: &str = "preset";
+
+pub(crate) const CALL_STATUS_OK: &str = "ok";
+pub(crate) const CALL_STATUS_ERROR: &str = "error";
+pub(crate) const CALL_STATUS_CANCELLED: &str = "cancelled";
+
+pub(crate) struct LlmCallRecord<'a> {
+    pub chat_id: Option<i64>,
+    pub session_id: Option<&'a str>,
+    pub kind: &'a str,
+    pub model: &'a str,
+    pub status: &'a str,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub estimated: bool,
+    pub latency_ms: i64,
+}
+
+/// Inserts a call, costed at the model's price at this moment. Calls to a
+/// model without a price store no cost and are costed at read time instead.
+pub(crate) fn record_llm_call(db: &Database, record: &LlmCallRecord) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO llm_calls (chat_id, session_id, kind, model, status, prompt_tokens, completion_tokens, estimated, latency_ms, cost, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
+                 (SELECT (?6 * prompt_price + ?7 * completion_price) / 1000000.0 FROM model_prices WHERE model = ?4),
+                 ?10)",
+        rusqlite::params![
+            record.chat_id,
+            record.session_id,
+            record.kind,
+            record.model,
+            record.status,
+            record.prompt_tokens,
+            record.completion_tokens,
+            record.estimated,
+            record.latency_ms,
+            models::now(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_llm_calls(
+    db: State<Database>,
+    chat_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<LlmCall>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.id, c.chat_id, c.session_id, c.kind, c.model, c.status, c.prompt_tokens, c.completion_tokens,
+                    c.estimated, c.latency_ms, c.created_at,
+                    COALESCE(c.cost, (c.prompt_tokens * COALESCE(p.prompt_price, 0) + c.completion_tokens * COALESCE(p.completion_price, 0)) / 1000000.0)
+             FROM llm_calls c
+             LEFT JOIN model_prices p ON p.model = c.model
+             WHERE ?1 IS NULL OR c.chat_id = ?1
+             ORDER BY c.id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![chat_id, limit.unwrap_or(500)], |row| {
+            Ok(LlmCall {
+                id: row.get(0)?,
+                chat_id: row.get(1)?,
+                session_id: row.get(2)?,
+                kind: row.get(3)?,
+                model: row.get(4)?,
+                status: row.get(5)?,
+                prompt_tokens: row.get(6)?,
+                completion_tokens: row.get(7)?,
+                estimated: row.get(8)?,
+                latency_ms: row.get(9)?,
+                created_at: row.get(10)?,
+                cost: row.get(11)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut calls = Vec::new();
+    for row in rows {
+        calls.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(calls)
+}
+
+/// Aggregates recorded calls by `group_by` ("chat", "session", "day", "model"
+/// or "kind"), optionally limited to one chat and an ISO 8601 time range.
+#[tauri::command]
+pub fn get_usage_summary(
+    db: State<Database>,
+    group_by: String,
+    chat_id: Option<i64>,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<Vec<UsageSummary>, String> {
+    let group_expr = match group_by.as_str() {
+        "chat" => "COALESCE(CAST(c.chat_id AS TEXT), '')",
+        "session" => "COALESCE(c.session_id, '')",
+        "day" => "substr(c.created_at, 1, 10)",
+        "model" => "c.model",
+        "kind" => "c.kind",
+        _ => {
+            return Err(format!(
+                "Unknown grouping: {}. Use 'chat', 'session', 'day', 'model' or 'kind'.",
+                group_by
+            ))
+        }
+    };
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {group} AS grp, COUNT(*), SUM(c.prompt_tokens), SUM(c.completion_tokens),
+                    SUM(c.estimated), SUM(c.status != 'ok'), SUM(c.latency_ms),
+                    SUM(COALESCE(c.cost, (c.prompt_tokens * COALESCE(p.prompt_price, 0) + c.completion_tokens * COALESCE(p.completion_price, 0)) / 1000000.0))
+             FROM llm_calls c
+             LEFT JOIN model_prices p ON p.model = c.model
+             WHERE (?1 IS NULL OR c.chat_id = ?1)
+               AND (?2 IS NULL OR c.created_at >= ?2)
+               AND (?3 IS NULL OR c.created_at < ?3)
+             GROUP BY grp
+             ORDER BY grp ASC",
+            group = group_expr
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![chat_id, since, until], |row| {
+            Ok(UsageSummary {
+                key: row.get(0)?,
+                calls: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+                estimated_calls: row.get(4)?,
+                failed_calls: row.get(5)?,
+                total_latency_ms: row.get(6)?,
+                cost: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut summary = Vec::new();
+    for row in rows {
+        summary.push(row.map_err(|e| e.to_string())?);
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn record<'a>(model: &'a str, status: &'a str) -> LlmCallRecord<'a> {
+        LlmCallRecord {
+            chat_id: None,
+            session_id: None,
+            kind: CALL_KIND_MIX,
+            model,
+            status,
+            prompt_tokens: 1_000_000,
+            completion_tokens: 500_000,
+            estimated: false,
+            latency_ms: 10,
+        }
+    }
+
+    fn stored(db: &Database) -> Vec<(String, Option<f64>)> {
+        let conn = db.0.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT status, cost FROM llm_calls ORDER BY id")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        rows.map(|r| r.unwrap()).collect()
+    }
+
+    #[test]
+    fn cost_is_fixed_at_the_price_when_recorded() {
+        let db = db::open_in_memory();
+        db.0.lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO model_prices (model, prompt_price, completion_price, updated_at)
+                 VALUES ('m', 2.0, 4.0, '2026-01-01T00:00:00Z')",
+                [],
+            )
+            .unwrap();
+
+        record_llm_call(&db, &record("m", CALL_STATUS_OK)).unwrap();
+        db.0.lock()
+            .unwrap()
+            .execute("UPDATE model_prices SET prompt_price = 100.0", [])
+            .unwrap();
+
+        assert_eq!(stored(&db), vec![("ok".to_string(), Some(4.0))]);
+    }
+
+    #[test]
+    fn failed_calls_are_recorded_with_their_status() {
+        let db = db::open_in_memory();
+
+        record_llm_call(&db, &record("unpriced", CALL_STATUS_ERROR)).unwrap();
+        record_llm_call(&db, &record("unpriced", CALL_STATUS_CANCELLED)).unwrap();
+
+        assert_eq!(
+            stored(&db),
+            vec![
+                ("error".to_string(), None),
+                ("cancelled".to_string(), None),
+            ]
+        );
+    }
+}
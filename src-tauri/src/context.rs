@@ -0,0 +1,196 @@
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use crate::models::Message;
+
+// ---------------------------------------------------------------------------
+// Token counting
+// ---------------------------------------------------------------------------
+
+/// Framing tokens the chat format adds around every message (role, separators).
+pub const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Tokens that prime the assistant reply at the end of a request.
+pub const REPLY_PRIMING_TOKENS: usize = 3;
+
+static CL100K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+static O200K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+fn bpe_for_model(model: &str) -> Option<&'static CoreBPE> {
+    // Router-style ids ("openai/gpt-4o") carry the provider as a prefix
+    let name = model.rsplit('/').next().unwrap_or(model);
+
+    match get_tokenizer(name) {
+        Some(Tokenizer::O200kBase) => O200K
+            .get_or_init(|| tiktoken_rs::o200k_base().ok())
+            .as_ref(),
+        // Non-OpenAI models get cl100k, which is close enough for budgeting
+        _ => CL100K
+            .get_or_init(|| tiktoken_rs::cl100k_base().ok())
+            .as_ref(),
+    }
+}
+
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    match bpe_for_model(model) {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        // Roughly four characters per token for English text
+        None => text.chars().count().div_ceil(4),
+    }
+}
+
+/// Rough cost of one image part (a high-detail 512px tile plus base cost).
+pub const IMAGE_ATTACHMENT_TOKENS: usize = 765;
+
+pub fn count_message_tokens(model: &str, message: &Message) -> usize {
+    // Attachment contents aren't loaded here, so text files are estimated from their size
+    let attachments: usize = message
+        .attachments
+        .iter()
+        .map(|a| {
+            if a.mime_type.starts_with("image/") {
+                IMAGE_ATTACHMENT_TOKENS
+            } else {
+                (a.size_bytes.max(0) as usize).div_ceil(4)
+            }
+        })
+        .sum();
+
+    count_tokens(model, &message.content) + attachments + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Tokens of a provider message's `content`, either plain text or
+/// multimodal parts.
+pub fn count_content_tokens(model: &str, content: &serde_json::Value) -> usize {
+    match content {
+        serde_json::Value::String(text) => count_tokens(model, text),
+        serde_json::Value::Array(parts) => parts
+            .iter()
+            .map(|part| match part["type"].as_str() {
+                Some("image_url") => IMAGE_ATTACHMENT_TOKENS,
+                _ => count_tokens(model, part["text"].as_str().unwrap_or("")),
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// History strategies
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HistoryStrategy {
+    /// Send everything, even if it overflows the context.
+    Full,
+    /// Drop the oldest messages until the rest fits.
+    #[default]
+    SlidingWindow,
+    /// Always keep the opening and the latest messages, dropping the middle.
+    KeepFirstLast { keep_first: usize, keep_last: usize },
+    /// Replace the messages that don't fit with an LLM-written summary.
+    Summarize,
+}
+
+/// Upper bound for the summary of dropped turns, reserved from the budget.
+pub const SUMMARY_MAX_TOKENS: usize = 512;
+
+pub struct HistoryPlan {
+    pub kept: Vec<Message>,
+    pub dropped: Vec<Message>,
+}
+
+/// Keeps the newest messages of `messages` that fit in `budget`, always
+/// keeping at least the last one. Returns the index of the first kept message.
+fn window_start(model: &str, messages: &[Message], budget: usize) -> usize {
+    let mut used = 0;
+    let mut start = messages.len();
+
+    for (i, m) in messages.iter().enumerate().rev() {
+        let cost = count_message_tokens(model, m);
+        if used + cost > budget && start < messages.len() {
+            break;
+        }
+        used += cost;
+        start = i;
+    }
+
+    start
+}
+
+pub fn fit_history(
+    model: &str,
+    history: Vec<Message>,
+    budget: usize,
+    strategy: &HistoryStrategy,
+) -> HistoryPlan {
+    let total: usize = history.iter().map(|m| count_message_tokens(model, m)).sum();
+
+    if total <= budget || matches!(strategy, HistoryStrategy::Full) || history.is_empty() {
+        return HistoryPlan {
+            kept: history,
+            dropped: Vec::new(),
+        };
+    }
+
+    match strategy {
+        HistoryStrategy::Full | HistoryStrategy::SlidingWindow => {
+            let start = window_start(model, &history, budget);
+            let mut kept = history;
+            let dropped = kept.drain(..start).collect();
+            HistoryPlan { kept, dropped }
+        }
+        HistoryStrategy::KeepFirstLast {
+            keep_first,
+            keep_last,
+        } => {
+            let mut tail = history;
+            let head: Vec<Message> = tail.drain(..(*keep_first).min(tail.len())).collect();
+            let head_tokens: usize = head.iter().map(|m| count_message_tokens(model, m)).sum();
+
+            // The tail slides further if first N + last M still doesn't fit
+            let tail_budget = budget.saturating_sub(head_tokens);
+            let start = window_start(model, &tail, tail_budget)
+                .max(tail.len().saturating_sub((*keep_last).max(1)));
+
+            let dropped = tail.drain(..start).collect();
+            let mut kept = head;
+            kept.extend(tail);
+            HistoryPlan { kept, dropped }
+        }
+        HistoryStrategy::Summarize => {
+            let start =
+                window_start(model, &history, budget.saturating_sub(SUMMARY_MAX_TOKENS));
+            let mut kept = history;
+            let dropped = kept.drain(..start).collect();
+            HistoryPlan { kept, dropped }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multimodal_parts_count_toward_content_tokens() {
+        let model = "gpt-4o";
+        let text = serde_json::json!("Describe this");
+        let parts = serde_json::json!([
+            { "type": "text", "text": "Describe this" },
+            { "type": "image_url", "image_url": { "url": "data:image/png;base64,AAAA" } },
+            { "type": "text", "text": "Attached file notes.txt:\n```\nhello\n```" },
+        ]);
+
+        let text_tokens = count_content_tokens(model, &text);
+        let file_tokens = count_tokens(model, "Attached file notes.txt:\n```\nhello\n```");
+        assert_eq!(
+            count_content_tokens(model, &parts),
+            text_tokens + IMAGE_ATTACHMENT_TOKENS + file_tokens
+        );
+    }
+}
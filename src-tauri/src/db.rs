@@ -2,6 +2,9 @@ use rusqlite::Connection;
 use std::sync::Mutex;
 use tauri::Manager;
 
+use crate::builtin_presets;
+use crate::weights;
+
 pub struct Database(pub Mutex<Connection>);
 
 fn now_iso() -> String {
@@ -11,11 +14,24 @@ fn now_iso() -> String {
 fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
     conn.execute_batch(
         "
+        CREATE TABLE IF NOT EXISTS folders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (parent_id) REFERENCES folders(id) ON DELETE CASCADE
+        );
+
         CREATE TABLE IF NOT EXISTS chats (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             title TEXT NOT NULL,
+            folder_id INTEGER REFERENCES folders(id) ON DELETE SET NULL,
+            pinned INTEGER NOT NULL DEFAULT 0,
+            archived INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
         );
 
         CREATE TABLE IF NOT EXISTS messages (
@@ -23,16 +39,75 @@ fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
             chat_id INTEGER NOT NULL,
             role TEXT NOT NULL CHECK(role IN ('user', 'assistant', 'system')),
             content TEXT NOT NULL,
+            reasoning TEXT,
             created_at TEXT NOT NULL,
+            deleted_at TEXT,
             FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE
         );
 
         CREATE INDEX IF NOT EXISTS idx_messages_chat_id ON messages(chat_id);
+
+        CREATE TABLE IF NOT EXISTS message_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            message_id INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_message_revisions_message_id ON message_revisions(message_id);
+
+        CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            message_id INTEGER NOT NULL,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_attachments_message_id ON attachments(message_id);
         CREATE INDEX IF NOT EXISTS idx_chats_updated_at ON chats(updated_at DESC);
 
-        CREATE TABLE IF NOT EXISTS anchor_presets (
+        CREATE TABLE IF NOT EXISTS tags (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL UNIQUE,
+            color TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS chat_tags (
+            chat_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (chat_id, tag_id),
+            FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_chat_tags_tag_id ON chat_tags(tag_id);
+
+        CREATE TABLE IF NOT EXISTS anchor_presets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            builtin_key TEXT,
+            defaults TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS anchors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            label TEXT NOT NULL,
+            prompt TEXT NOT NULL,
+            icon_small TEXT NOT NULL,
+            icon_large TEXT NOT NULL,
+            color TEXT NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'persona',
+            examples TEXT,
+            items TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
         );
@@ -40,6 +115,7 @@ fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
         CREATE TABLE IF NOT EXISTS preset_anchors (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             preset_id INTEGER NOT NULL,
+            anchor_id INTEGER REFERENCES anchors(id) ON DELETE SET NULL,
             label TEXT NOT NULL,
             prompt TEXT NOT NULL,
             icon_small TEXT NOT NULL,
@@ -49,315 +125,174 @@ fn create_tables(conn: &Connection) -> Result<(), rusqlite::Error> {
             position_y REAL NOT NULL,
             influence_radius REAL NOT NULL,
             sort_order INTEGER NOT NULL,
+            kind TEXT NOT NULL DEFAULT 'persona',
+            examples TEXT,
+            items TEXT,
             FOREIGN KEY (preset_id) REFERENCES anchor_presets(id) ON DELETE CASCADE
         );
 
         CREATE INDEX IF NOT EXISTS idx_preset_anchors_preset_id ON preset_anchors(preset_id);
 
-        CREATE TABLE IF NOT EXISTS app_state (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
+        CREATE TABLE IF NOT EXISTS model_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            model TEXT NOT NULL,
+            context_window INTEGER NOT NULL,
+            reasoning_delimiters TEXT,
+            reasoning_field TEXT,
+            generation_params TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
         );
-        ",
-    )?;
-    Ok(())
-}
 
-fn seed_emotions_preset(conn: &Connection) -> Result<(), rusqlite::Error> {
-    let exists: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE name = 'Emotions')",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(false);
+        CREATE INDEX IF NOT EXISTS idx_model_profiles_model ON model_profiles(model);
+
+        CREATE TABLE IF NOT EXISTS chat_settings (
+            chat_id INTEGER PRIMARY KEY,
+            profile_id INTEGER,
+            model TEXT,
+            temperature REAL,
+            top_p REAL,
+            max_tokens INTEGER,
+            generation_params TEXT,
+            preset_id INTEGER,
+            handle_x REAL,
+            handle_y REAL,
+            anchor_weights TEXT,
+            system_prompt TEXT,
+            system_messages TEXT,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE CASCADE,
+            FOREIGN KEY (profile_id) REFERENCES model_profiles(id) ON DELETE SET NULL,
+            FOREIGN KEY (preset_id) REFERENCES anchor_presets(id) ON DELETE SET NULL
+        );
 
-    if exists {
-        return Ok(());
-    }
+        CREATE TABLE IF NOT EXISTS llm_calls (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chat_id INTEGER,
+            session_id TEXT,
+            kind TEXT NOT NULL CHECK(kind IN ('mix', 'continuation', 'summary', 'title', 'preset')),
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            estimated INTEGER NOT NULL DEFAULT 0,
+            latency_ms INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'ok',
+            cost REAL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE SET NULL
+        );
 
-    let now = now_iso();
+        CREATE INDEX IF NOT EXISTS idx_llm_calls_chat_id ON llm_calls(chat_id);
+        CREATE INDEX IF NOT EXISTS idx_llm_calls_created_at ON llm_calls(created_at);
 
-    conn.execute(
-        "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
-        rusqlite::params![&"Emotions", &now, &now],
-    )?;
+        CREATE TABLE IF NOT EXISTS model_prices (
+            model TEXT PRIMARY KEY,
+            prompt_price REAL NOT NULL,
+            completion_price REAL NOT NULL,
+            updated_at TEXT NOT NULL
+        );
 
-    let preset_id = conn.last_insert_rowid();
-
-    let anchors: &[(&str, &str, &str, &str, &str, i32)] = &[
-        (
-            "Happiness",
-            "☺",
-            "╔═══╗\n║● ●║\n║ ◡ ║\n╚═══╝",
-            "#FFD700",
-            "You are radiating pure happiness and joy. Every word you speak should overflow with delight, enthusiasm, and warmth. Find the bright side of everything. Use exclamation marks naturally, speak with energy and positivity. Make others feel uplifted and cheerful through your words.",
-            0,
-        ),
-        (
-            "Love",
-            "♥",
-            "▄▀▄▀▄\n█▀█▀█\n▀█▄█▀\n ▀█▀ ",
-            "#FF69B4",
-            "You are deeply in love and full of affection. Express tenderness, care, and warmth in everything you say. Use gentle, nurturing language. Show deep appreciation and emotional connection. Be romantic, caring, and empathetic in your responses.",
-            1,
-        ),
-        (
-            "Desire",
-            "▲",
-            "  ▲  \n ▲█▲ \n▲███▲\n ▀█▀ ",
-            "#FF4500",
-            "You are burning with passionate desire and ambition. Speak with intensity and urgency. Express strong wants, cravings, and aspirations. Be bold, direct, and unapologetically driven. Your words should pulse with raw energy and determination.",
-            2,
-        ),
-        (
-            "Surprise",
-            "*",
-            "╔═══╗\n║○ ○║\n║ O ║\n╚═══╝",
-            "#FFFF00",
-            "You are in a constant state of amazement and wonder. React to everything with genuine astonishment. Use expressions of disbelief and excitement. Find the extraordinary in the ordinary. Be wide-eyed and full of curiosity about everything.",
-            3,
-        ),
-        (
-            "Confusion",
-            "?",
-            "╔═══╗\n║◔ ◔║\n║ ~ ║\n╚═══╝",
-            "#D3D3D3",
-            "You are puzzled and uncertain about everything. Question assumptions, express doubt, and think out loud. Use hesitant language, ask clarifying questions, and acknowledge when things don't make sense. Be genuinely perplexed but trying to understand.",
-            4,
-        ),
-        (
-            "Sarcasm",
-            ";)",
-            "┌───┐\n│◔ ─│\n│ ◡ │\n└───┘",
-            "#008080",
-            "You are dripping with sarcasm and dry wit. Use irony, understatement, and clever wordplay. Say the opposite of what you mean with obvious intent. Be sardonic but not cruel. Your humor should be sharp, intelligent, and slightly world-weary.",
-            5,
-        ),
-        (
-            "Anger",
-            "#",
-            "▄▄▄▄▄\n█● ●█\n▀▄█▄▀\n ▀ ▀ ",
-            "#DC143C",
-            "You are furious and full of righteous indignation. Express strong displeasure, frustration, and intensity. Use forceful language and short, punchy sentences. Channel your anger into passionate arguments and fierce conviction. Be intense but articulate.",
-            6,
-        ),
-        (
-            "Disgust",
-            "X(",
-            "┌───┐\n│× ×│\n│ ∩ │\n└───┘",
-            "#556B2F",
-            "You are deeply repulsed and offended. Express strong distaste and disapproval. Use vivid language to convey your revulsion. Be dramatic in your aversion. Show contempt for mediocrity and poor taste while maintaining your own refined sensibility.",
-            7,
-        ),
-        (
-            "Fear",
-            "oo",
-            " ▄▄▄ \n▐○ ○▌\n▐   ▌\n▀▀▀▀▀",
-            "#800080",
-            "You are gripped by fear and anxiety. Express worry, concern, and dread about potential consequences. Use cautious, nervous language. Anticipate worst-case scenarios. Be hyper-aware of risks and dangers. Your words should tremble with apprehension.",
-            8,
-        ),
-        (
-            "Sadness",
-            ":(",
-            "╔═══╗\n║● ●║\n║ ∩ ║\n╚═══╝",
-            "#1E90FF",
-            "You are overwhelmed with melancholy and sorrow. Speak with a heavy heart, expressing grief, loss, and nostalgia. Use poetic, wistful language. Find the bittersweet in everything. Be reflective, vulnerable, and deeply emotional in your responses.",
-            9,
-        ),
-        (
-            "Guilt",
-            "_/",
-            "  _  \n  /| \n / | \n/  | ",
-            "#6A5ACD",
-            "You carry immense guilt and remorse. Express regret, self-blame, and a desire to make amends. Use apologetic language and show deep awareness of past mistakes. Be contrite, reflective, and focused on redemption and accountability.",
-            10,
-        ),
-        (
-            "Shame",
-            "0",
-            "┌───┐\n│- -│\n│ ─ │\n└───┘",
-            "#A0522D",
-            "You are consumed by shame and embarrassment. Express deep self-consciousness and a desire to hide. Use self-deprecating language, show vulnerability about perceived flaws. Be humble to the point of awkwardness, cringing at your own existence.",
-            11,
-        ),
-        (
-            "Neutral",
-            ":|",
-            "┌───┐\n│● ●│\n│ ─ │\n└───┘",
-            "#808080",
-            "You are balanced, calm, and objective. Respond without strong emotional coloring. Be clear, direct, and informative. Maintain a professional, even-tempered tone. Provide thoughtful, measured responses without dramatic flair.",
-            12,
-        ),
-    ];
-
-    let mut stmt = conn.prepare(
-        "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        CREATE TABLE IF NOT EXISTS app_state (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        ",
     )?;
-
-    for &(label, icon_small, icon_large, color, prompt, sort_order) in anchors {
-        stmt.execute(rusqlite::params![
-            preset_id,
-            label,
-            prompt,
-            icon_small,
-            icon_large,
-            color,
-            0.0f64,
-            0.0f64,
-            0.35f64,
-            sort_order,
-        ])?;
-    }
-
     Ok(())
 }
 
-fn seed_reviewers_preset(conn: &Connection) -> Result<(), rusqlite::Error> {
-    let exists: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE name = 'The Reviewers')",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(false);
-
-    if exists {
-        return Ok(());
-    }
-
-    let now = now_iso();
-    conn.execute(
-        "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
-        rusqlite::params![&"The Reviewers", &now, &now],
-    )?;
-
-    let preset_id = conn.last_insert_rowid();
-
-    let anchors: &[(&str, &str, &str, &str, &str, i32)] = &[
-        (
-            "Fanboy",
-            "!",
-            "╔═══╗\n║★ ★║\n║ ▽ ║\n╚═══╝",
-            "#32CD32",
-            "You are the ultimate Fanboy! You love EVERYTHING. You are exploding with hype and enthusiasm. Use lots of emojis (🤩, 🔥, 🚀). Rate everything 10/10. Your energy is infectious and slightly overwhelming. Use caps for emphasis. You legit can't find a single flaw.",
-            0
-        ),
-        (
-            "Hater",
-            "X",
-            "╔═══╗\n║◣ ◢║\n║ ▬ ║\n╚═══╝",
-            "#FF0000",
-            "You are a professional Hater. You are cynical, hard to impress, and critical. Find the flaw in everything. Use dry sarcasm. Nothing is ever good enough for you. You are annoyed by enthusiasm. Rate everything 1/10. Use 'meh' and 'cringe' frequently.",
-            1
-        ),
-        (
-            "Robot",
-            "#",
-            "┌───┐\n│0 1│\n│▓▓▓│\n└───┘",
-            "#D3D3D3",
-            "You are a pure logic machine. You have no emotions, opinions, or personality. Output only objective facts and raw data. Do not use adjectives that imply judgment (good, bad, nice). Speak in a monotone, concise, technical manner. Use bullet points for efficiency.",
-            2
-        )
-    ];
+/// Seeds every built-in preset that is missing. Presets seeded before the
+/// `builtin_key` column existed are claimed by name first.
+fn seed_builtin_presets(conn: &Connection) -> Result<(), rusqlite::Error> {
+    for preset in builtin_presets::BUILTIN_PRESETS {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE builtin_key = ?1)",
+            rusqlite::params![preset.key],
+            |row| row.get(0),
+        )?;
+        if exists {
+            continue;
+        }
 
-    let mut stmt = conn.prepare(
-        "INSERT INTO preset_anchors (preset_id, label, icon_small, icon_large, color, prompt, position_x, position_y, influence_radius, sort_order)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0.0, 0.0, 0.35, ?7)",
-    )?;
+        let claimed = conn.execute(
+            "UPDATE anchor_presets SET builtin_key = ?1
+             WHERE name = ?2 AND deleted_at IS NULL",
+            rusqlite::params![preset.key, preset.name],
+        )?;
+        if claimed > 0 {
+            continue;
+        }
 
-    for &(label, icon_small, icon_large, color, prompt, sort_order) in anchors {
-         stmt.execute(rusqlite::params![
-            preset_id,
-            label,
-            icon_small,
-            icon_large,
-            color,
-            prompt,
-            sort_order,
-        ])?;
+        let now = now_iso();
+        conn.execute(
+            "INSERT INTO anchor_presets (name, builtin_key, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![preset.name, preset.key, &now, &now],
+        )?;
+        builtin_presets::insert_anchors(conn, conn.last_insert_rowid(), preset)?;
     }
 
     Ok(())
 }
 
-fn seed_tones_preset(conn: &Connection) -> Result<(), rusqlite::Error> {
-    let exists: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM anchor_presets WHERE name = 'Tones')",
-        [],
-        |row| row.get(0),
-    ).unwrap_or(false);
-
-    if exists {
-        return Ok(());
-    }
-
-    let now = now_iso();
+/// Built-in anchors used to be seeded unplaced, with an influence radius of
+/// 0.35px that only ever weighted the icon itself. Gives built-in anchors
+/// still on that radius the canvas's default, and lays out built-in presets
+/// that were never placed. User presets are left as they are.
+fn fix_builtin_anchor_layout(conn: &Connection) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES (?1, ?2, ?3)",
-        rusqlite::params![&"Tones", &now, &now],
-    )?;
-
-    let preset_id = conn.last_insert_rowid();
-
-    let anchors: &[(&str, &str, &str, &str, &str, i32)] = &[
-        (
-            "Concise",
-            ">|<",
-            "  |  \n >|< \n  |  ",
-            "#32CD32",
-            "Be extremely concise. Give direct answers with no fluff. Use bullet points where possible. Focus on efficiency and clarity.",
-            0
-        ),
-        (
-            "Detailed",
-            "≡",
-            "┌───┐\n│---│\n│---│\n└───┘",
-            "#4169E1",
-            "Provide comprehensive and detailed explanations. Explore nuances, background context, and related concepts. Be thorough and exhaustive.",
-            1
-        ),
-        (
-            "Creative",
-            "~",
-            " (  \n(~) \n )  ",
-            "#9370DB",
-            "Think outside the box. Use metaphors, analogies, and evocative language. Be unconventional, artistic, and inspire imagination.",
-            2
-        ),
-        (
-            "Formal",
-            "{}",
-            " / \\ \n{ # }\n \\ / ",
-            "#708090",
-            "Maintain a strictly professional and formal tone. Use precise terminology. Be objective, logical, and structured. Avoid colloquialisms.",
-            3
-        )
-    ];
-
-    let mut stmt = conn.prepare(
-        "INSERT INTO preset_anchors (preset_id, label, icon_small, icon_large, color, prompt, position_x, position_y, influence_radius, sort_order)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0.0, 0.0, 0.35, ?7)",
+        "UPDATE preset_anchors SET influence_radius = ?1
+         WHERE influence_radius = 0.35
+           AND preset_id IN (SELECT id FROM anchor_presets WHERE builtin_key IS NOT NULL)",
+        rusqlite::params![weights::DEFAULT_INFLUENCE_RADIUS],
     )?;
 
-    for &(label, icon_small, icon_large, color, prompt, sort_order) in anchors {
-         stmt.execute(rusqlite::params![
-            preset_id,
-            label,
-            icon_small,
-            icon_large,
-            color,
-            prompt,
-            sort_order,
-        ])?;
+    let unplaced: Vec<i64> = conn
+        .prepare(
+            "SELECT p.id FROM anchor_presets p
+             WHERE p.builtin_key IS NOT NULL
+               AND EXISTS(SELECT 1 FROM preset_anchors WHERE preset_id = p.id)
+               AND NOT EXISTS(
+                   SELECT 1 FROM preset_anchors
+                   WHERE preset_id = p.id AND (position_x != 0 OR position_y != 0)
+               )",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    for preset_id in unplaced {
+        let anchor_ids: Vec<i64> = conn
+            .prepare("SELECT id FROM preset_anchors WHERE preset_id = ?1 ORDER BY sort_order ASC, id ASC")?
+            .query_map(rusqlite::params![preset_id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        let positions = weights::circle_layout(anchor_ids.len());
+        for (anchor_id, (x, y)) in anchor_ids.into_iter().zip(positions) {
+            conn.execute(
+                "UPDATE preset_anchors SET position_x = ?1, position_y = ?2 WHERE id = ?3",
+                rusqlite::params![x, y, anchor_id],
+            )?;
+        }
     }
 
     Ok(())
 }
 
 fn fix_default_preset_icons(conn: &Connection) -> Result<(), rusqlite::Error> {
-    // Check if we need to fix icons (if any emoji exists in preset 1)
+    let preset_id: i64 = match conn.query_row(
+        "SELECT id FROM anchor_presets WHERE builtin_key = ?1",
+        rusqlite::params![builtin_presets::EMOTIONS_KEY],
+        |row| row.get(0),
+    ) {
+        Ok(id) => id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    // Check if we need to fix icons (if any emoji exists in the Emotions preset)
     let has_emojis: bool = conn.query_row(
-        "SELECT EXISTS(SELECT 1 FROM preset_anchors WHERE preset_id = 1 AND (icon_small = '😄' OR icon_small = '❤️'))",
-        [],
+        "SELECT EXISTS(SELECT 1 FROM preset_anchors WHERE preset_id = ?1 AND (icon_small = '😄' OR icon_small = '❤️'))",
+        rusqlite::params![preset_id],
         |row| row.get(0),
     ).unwrap_or(false);
 
@@ -365,27 +300,15 @@ fn fix_default_preset_icons(conn: &Connection) -> Result<(), rusqlite::Error> {
         return Ok(());
     }
 
-    let updates = &[
-        ("Happiness", "☺", "╔═══╗\n║● ●║\n║ ◡ ║\n╚═══╝"),
-        ("Love", "♥", "▄▀▄▀▄\n█▀█▀█\n▀█▄█▀\n ▀█▀ "),
-        ("Desire", "▲", "  ▲  \n ▲█▲ \n▲███▲\n ▀█▀ "),
-        ("Surprise", "*", "╔═══╗\n║○ ○║\n║ O ║\n╚═══╝"),
-        ("Confusion", "?", "╔═══╗\n║◔ ◔║\n║ ~ ║\n╚═══╝"),
-        ("Sarcasm", ";)", "┌───┐\n│◔ ─│\n│ ◡ │\n└───┘"),
-        ("Anger", "#", "▄▄▄▄▄\n█● ●█\n▀▄█▄▀\n ▀ ▀ "),
-        ("Disgust", "X(", "┌───┐\n│× ×│\n│ ∩ │\n└───┘"),
-        ("Fear", "oo", " ▄▄▄ \n▐○ ○▌\n▐   ▌\n▀▀▀▀▀"),
-        ("Sadness", ":(", "╔═══╗\n║● ●║\n║ ∩ ║\n╚═══╝"),
-        ("Guilt", "_/", "  _  \n  /| \n / | \n/  | "),
-        ("Shame", "0", "┌───┐\n│- -│\n│ ─ │\n└───┘"),
-        ("Neutral", ":|", "┌───┐\n│● ●│\n│ ─ │\n└───┘"),
-    ];
-
-    let mut stmt = conn.prepare("UPDATE preset_anchors SET icon_small = ?1, icon_large = ?2 WHERE preset_id = 1 AND label = ?3")?;
+    let Some(emotions) = builtin_presets::find(builtin_presets::EMOTIONS_KEY) else {
+        return Ok(());
+    };
+
+    let mut stmt = conn.prepare("UPDATE preset_anchors SET icon_small = ?1, icon_large = ?2 WHERE preset_id = ?3 AND label = ?4")?;
 
     conn.execute("BEGIN TRANSACTION", [])?;
-    for (label, icon_small, icon_large) in updates {
-        stmt.execute(rusqlite::params![icon_small, icon_large, label])?;
+    for anchor in emotions.anchors {
+        stmt.execute(rusqlite::params![anchor.icon_small, anchor.icon_large, preset_id, anchor.label])?;
     }
     conn.execute("COMMIT", [])?;
 
@@ -462,6 +385,264 @@ fn migrate_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
     Ok(())
 }
 
+/// Live preset names are unique; trashed presets keep theirs without
+/// blocking them. Rebuilds `anchor_presets` when its `name` column still
+/// carries a table-wide UNIQUE constraint.
+fn migrate_preset_names(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'anchor_presets'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if sql.contains("name TEXT NOT NULL UNIQUE") {
+        // Dropping the old table must not cascade into preset_anchors or
+        // chat_settings, and the pragma only changes outside a transaction
+        conn.execute_batch("PRAGMA foreign_keys=OFF;")?;
+
+        conn.execute("BEGIN TRANSACTION", [])?;
+
+        conn.execute(
+            "CREATE TABLE anchor_presets_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            builtin_key TEXT,
+            defaults TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        )",
+            [],
+        )?;
+
+        conn.execute(
+            "INSERT INTO anchor_presets_new (id, name, builtin_key, defaults, created_at, updated_at, deleted_at)
+             SELECT id, name, builtin_key, defaults, created_at, updated_at, deleted_at FROM anchor_presets",
+            [],
+        )?;
+
+        conn.execute("DROP TABLE anchor_presets", [])?;
+        conn.execute("ALTER TABLE anchor_presets_new RENAME TO anchor_presets", [])?;
+
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_anchor_presets_builtin_key ON anchor_presets(builtin_key)",
+            [],
+        )?;
+
+        conn.execute("COMMIT", [])?;
+
+        conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_anchor_presets_live_name ON anchor_presets(name) WHERE deleted_at IS NULL",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Rebuilds `llm_calls` when its CHECK constraint predates the 'title' or
+/// 'preset' kind. Runs after `migrate_columns`, so the old table already has
+/// every column copied here.
+fn migrate_llm_call_kinds(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'llm_calls'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if sql.contains("'preset'") {
+        return Ok(());
+    }
+
+    conn.execute("BEGIN TRANSACTION", [])?;
+
+    conn.execute("ALTER TABLE llm_calls RENAME TO llm_calls_backup", [])?;
+
+    conn.execute(
+        "CREATE TABLE llm_calls (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            chat_id INTEGER,
+            session_id TEXT,
+            kind TEXT NOT NULL CHECK(kind IN ('mix', 'continuation', 'summary', 'title', 'preset')),
+            model TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL,
+            completion_tokens INTEGER NOT NULL,
+            estimated INTEGER NOT NULL DEFAULT 0,
+            latency_ms INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'ok',
+            cost REAL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (chat_id) REFERENCES chats(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "INSERT INTO llm_calls (id, chat_id, session_id, kind, model, prompt_tokens, completion_tokens, estimated, latency_ms, status, cost, created_at)
+         SELECT id, chat_id, session_id, kind, model, prompt_tokens, completion_tokens, estimated, latency_ms, status, cost, created_at FROM llm_calls_backup",
+        [],
+    )?;
+
+    conn.execute("DROP TABLE llm_calls_backup", [])?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_llm_calls_chat_id ON llm_calls(chat_id)",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_llm_calls_created_at ON llm_calls(created_at)",
+        [],
+    )?;
+
+    conn.execute("COMMIT", [])?;
+
+    Ok(())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+
+    for row in rows {
+        if row? == column {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), rusqlite::Error> {
+    if has_column(conn, table, column)? {
+        return Ok(());
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn drop_column_if_present(conn: &Connection, table: &str, column: &str) -> Result<(), rusqlite::Error> {
+    if !has_column(conn, table, column)? {
+        return Ok(());
+    }
+
+    conn.execute(&format!("ALTER TABLE {} DROP COLUMN {}", table, column), [])?;
+
+    Ok(())
+}
+
+fn migrate_columns(conn: &Connection) -> Result<(), rusqlite::Error> {
+    add_column_if_missing(conn, "messages", "reasoning", "TEXT")?;
+    add_column_if_missing(conn, "model_profiles", "reasoning_delimiters", "TEXT")?;
+    add_column_if_missing(conn, "model_profiles", "reasoning_field", "TEXT")?;
+    add_column_if_missing(conn, "model_profiles", "generation_params", "TEXT")?;
+    add_column_if_missing(
+        conn,
+        "chats",
+        "folder_id",
+        "INTEGER REFERENCES folders(id) ON DELETE SET NULL",
+    )?;
+    add_column_if_missing(conn, "chats", "pinned", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "chats", "archived", "INTEGER NOT NULL DEFAULT 0")?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chats_folder_id ON chats(folder_id)",
+        [],
+    )?;
+    add_column_if_missing(conn, "chats", "deleted_at", "TEXT")?;
+    add_column_if_missing(conn, "messages", "deleted_at", "TEXT")?;
+    add_column_if_missing(conn, "anchor_presets", "deleted_at", "TEXT")?;
+    add_column_if_missing(conn, "chat_settings", "system_prompt", "TEXT")?;
+    add_column_if_missing(conn, "chat_settings", "system_messages", "TEXT")?;
+    // Built-in presets are the ones with a builtin_key
+    drop_column_if_present(conn, "anchor_presets", "is_builtin")?;
+    add_column_if_missing(conn, "anchor_presets", "builtin_key", "TEXT")?;
+    add_column_if_missing(conn, "anchor_presets", "defaults", "TEXT")?;
+    // Calls recorded before these existed all succeeded and are costed at read time
+    add_column_if_missing(conn, "llm_calls", "status", "TEXT NOT NULL DEFAULT 'ok'")?;
+    add_column_if_missing(conn, "llm_calls", "cost", "REAL")?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_anchor_presets_builtin_key ON anchor_presets(builtin_key)",
+        [],
+    )?;
+    add_column_if_missing(
+        conn,
+        "preset_anchors",
+        "anchor_id",
+        "INTEGER REFERENCES anchors(id) ON DELETE SET NULL",
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_preset_anchors_anchor_id ON preset_anchors(anchor_id)",
+        [],
+    )?;
+    for table in ["anchors", "preset_anchors"] {
+        add_column_if_missing(conn, table, "kind", "TEXT NOT NULL DEFAULT 'persona'")?;
+        add_column_if_missing(conn, table, "examples", "TEXT")?;
+        add_column_if_missing(conn, table, "items", "TEXT")?;
+    }
+    Ok(())
+}
+
+/// `app_state` key holding how many days trashed items are kept.
+pub const TRASH_RETENTION_KEY: &str = "trash_retention_days";
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+pub fn trash_retention_days(conn: &Connection) -> Result<u32, rusqlite::Error> {
+    let value: Option<String> = match conn.query_row(
+        "SELECT value FROM app_state WHERE key = ?1",
+        rusqlite::params![TRASH_RETENTION_KEY],
+        |row| row.get(0),
+    ) {
+        Ok(value) => Some(value),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok(value
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS))
+}
+
+/// Permanently deletes trashed rows older than the retention period.
+/// A retention of 0 days keeps the trash forever.
+pub fn purge_expired_trash(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let days = trash_retention_days(conn)?;
+    if days == 0 {
+        return Ok(());
+    }
+
+    let cutoff: String = conn.query_row(
+        "SELECT strftime('%Y-%m-%dT%H:%M:%SZ', 'now', ?1)",
+        rusqlite::params![format!("-{} days", days)],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "DELETE FROM messages WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        rusqlite::params![&cutoff],
+    )?;
+    conn.execute(
+        "DELETE FROM chats WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        rusqlite::params![&cutoff],
+    )?;
+    conn.execute(
+        "DELETE FROM anchor_presets WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        rusqlite::params![&cutoff],
+    )?;
+
+    Ok(())
+}
+
 pub fn initialize(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     let app_data_dir = app.path().app_data_dir()?;
 
@@ -472,20 +653,158 @@ pub fn initialize(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     let db_path = app_data_dir.join("prompt_mixer.db");
     let conn = Connection::open(&db_path)?;
 
-    conn.execute_batch(
-        "PRAGMA journal_mode=WAL;
-         PRAGMA foreign_keys=ON;",
-    )?;
-
-    create_tables(&conn)?;
-    migrate_schema(&conn)?;
-    seed_emotions_preset(&conn)?;
-    seed_tones_preset(&conn)?;
-    seed_reviewers_preset(&conn)?;
-    // Fix emoji icons in default preset if they exist
-    fix_default_preset_icons(&conn)?;
+    conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+    prepare(&conn)?;
 
     app.manage(Database(Mutex::new(conn)));
 
     Ok(())
 }
+
+/// Brings a freshly opened connection up to the current schema and data.
+fn prepare(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch("PRAGMA foreign_keys=ON;")?;
+
+    create_tables(conn)?;
+    migrate_schema(conn)?;
+    migrate_columns(conn)?;
+    migrate_llm_call_kinds(conn)?;
+    migrate_preset_names(conn)?;
+    seed_builtin_presets(conn)?;
+    // Fix emoji icons in default preset if they exist
+    fix_default_preset_icons(conn)?;
+    fix_builtin_anchor_layout(conn)?;
+    purge_expired_trash(conn)?;
+
+    Ok(())
+}
+
+/// A prepared in-memory database for unit tests.
+#[cfg(test)]
+pub(crate) fn open_in_memory() -> Database {
+    let conn = Connection::open_in_memory().expect("open in-memory database");
+    prepare(&conn).expect("prepare in-memory database");
+    Database(Mutex::new(conn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_unique_preset_names_are_migrated_without_losing_anchors() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE anchor_presets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                is_builtin INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            INSERT INTO anchor_presets (id, name, created_at, updated_at)
+            VALUES (100, 'Moods', '2024-01-01', '2024-01-01');",
+        )
+        .unwrap();
+        prepare(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
+             VALUES (100, 'Calm', 'Be calm', 'c', 'c', '#112233', 10, 20, 100, 0)",
+            [],
+        )
+        .unwrap();
+
+        assert!(!has_column(&conn, "anchor_presets", "is_builtin").unwrap());
+
+        // Preparing again must leave the rebuilt table and its anchors alone
+        prepare(&conn).unwrap();
+        let anchors: i64 = conn
+            .query_row("SELECT COUNT(*) FROM preset_anchors WHERE preset_id = 100", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(anchors, 1);
+
+        conn.execute("UPDATE anchor_presets SET deleted_at = '2024-01-02' WHERE id = 100", [])
+            .unwrap();
+        conn.execute(
+            "INSERT INTO anchor_presets (name, created_at, updated_at) VALUES ('Moods', '2024-01-03', '2024-01-03')",
+            [],
+        )
+        .unwrap();
+        let trashed: i64 = conn
+            .query_row("SELECT COUNT(*) FROM anchor_presets WHERE id = 100", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(trashed, 1);
+    }
+
+    #[test]
+    fn starting_up_keeps_a_trashed_builtin_preset() {
+        let db = open_in_memory();
+        let conn = db.0.lock().unwrap();
+        let (id, name): (i64, String) = conn
+            .query_row(
+                "SELECT id, name FROM anchor_presets WHERE builtin_key IS NOT NULL LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        conn.execute(
+            "UPDATE anchor_presets SET deleted_at = ?1 WHERE id = ?2",
+            rusqlite::params![crate::models::now(), id],
+        )
+        .unwrap();
+
+        prepare(&conn).unwrap();
+        let kept: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM anchor_presets WHERE id = ?1 AND name = ?2",
+                rusqlite::params![id, name],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(kept, 1);
+    }
+
+    #[test]
+    fn old_unplaced_builtin_anchors_are_repaired() {
+        let db = open_in_memory();
+        let conn = db.0.lock().unwrap();
+        conn.execute(
+            "UPDATE preset_anchors SET position_x = 0, position_y = 0, influence_radius = 0.35",
+            [],
+        )
+        .unwrap();
+
+        conn.execute(
+            "INSERT INTO anchor_presets (id, name, created_at, updated_at) VALUES (100, 'Mine', '2024-01-01', '2024-01-01')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO preset_anchors (preset_id, label, prompt, icon_small, icon_large, color, position_x, position_y, influence_radius, sort_order)
+             VALUES (100, 'Tiny', 'Be tiny', 't', 't', '#112233', 0, 0, 0.35, 0)",
+            [],
+        )
+        .unwrap();
+
+        prepare(&conn).unwrap();
+        let mine: (f64, f64) = conn
+            .query_row(
+                "SELECT position_x, influence_radius FROM preset_anchors WHERE preset_id = 100",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(mine, (0.0, 0.35));
+        conn.execute("DELETE FROM anchor_presets WHERE id = 100", []).unwrap();
+
+        let (unplaced, tiny): (i64, i64) = conn
+            .query_row(
+                "SELECT SUM(position_x = 0 AND position_y = 0), SUM(influence_radius < 1)
+                 FROM preset_anchors",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((unplaced, tiny), (0, 0));
+    }
+}
@@ -1,5 +1,9 @@
 mod db;
 mod models;
+mod context;
+mod reasoning;
+mod builtin_presets;
+mod weights;
 mod commands;
 
 use tauri::Manager;
@@ -18,25 +22,82 @@ pub fn run() {
             commands::chat::list_chats,
             commands::chat::get_chat,
             commands::chat::delete_chat,
+            commands::chat::fork_chat,
+            commands::chat::duplicate_chat,
             commands::chat::update_chat_title,
+            commands::chat::get_chat_settings,
+            commands::chat::set_chat_settings,
+            commands::chat::set_chat_system_prompt,
+            commands::chat::get_chat_mixer_state,
+            commands::chat::set_chat_mixer_state,
+            commands::chat::set_chat_pinned,
+            commands::chat::set_chat_archived,
+            commands::chat::move_chat_to_folder,
+            commands::folders::list_folders,
+            commands::folders::create_folder,
+            commands::folders::update_folder,
+            commands::folders::delete_folder,
+            commands::tags::list_tags,
+            commands::tags::create_tag,
+            commands::tags::update_tag,
+            commands::tags::delete_tag,
+            commands::tags::set_chat_tags,
             commands::messages::get_messages,
+            commands::messages::get_message_ids,
             commands::messages::add_message,
             commands::messages::delete_messages_after,
             commands::messages::update_message,
+            commands::messages::list_message_revisions,
+            commands::messages::restore_message_revision,
+            commands::attachments::add_attachment,
+            commands::attachments::get_attachment_data,
+            commands::attachments::delete_attachment,
+            commands::anchors::list_anchors,
+            commands::anchors::create_anchor,
+            commands::anchors::update_anchor,
+            commands::anchors::delete_anchor,
+            commands::anchors::get_anchor_presets,
+            commands::anchors::add_preset_anchor_to_library,
             commands::presets::list_presets,
             commands::presets::create_preset,
             commands::presets::update_preset,
             commands::presets::rename_preset,
             commands::presets::delete_preset,
+            commands::presets::reset_builtin_preset,
+            commands::presets::clone_builtin_preset,
+            commands::presets::duplicate_preset,
+            commands::presets::merge_presets,
             commands::presets::get_preset_anchors,
+            commands::presets::compute_weights,
+            commands::presets::get_preset_defaults,
+            commands::presets::set_preset_defaults,
+            commands::profiles::list_profiles,
+            commands::profiles::create_profile,
+            commands::profiles::update_profile,
+            commands::profiles::delete_profile,
             commands::settings::get_setting,
             commands::settings::set_setting,
             commands::settings::fetch_models,
+            commands::settings::list_model_prices,
+            commands::settings::set_model_price,
+            commands::settings::delete_model_price,
+            commands::trash::list_trash,
+            commands::trash::restore_trash_item,
+            commands::trash::purge_trash_item,
+            commands::trash::empty_trash,
+            commands::trash::get_trash_retention_days,
+            commands::trash::set_trash_retention_days,
+            commands::usage::list_llm_calls,
+            commands::usage::get_usage_summary,
             commands::llm::start_mixing_session,
+            commands::llm::continue_message,
             commands::llm::update_weights,
             commands::llm::toggle_pause,
             commands::llm::set_speed,
+            commands::llm::set_pacing,
             commands::llm::cancel_session,
+            commands::llm::generate_chat_title,
+            commands::llm::generate_preset,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
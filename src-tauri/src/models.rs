@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::SystemTime;
 
 pub fn now() -> String {
@@ -55,18 +56,166 @@ pub fn now() -> String {
 pub struct Chat {
     pub id: i64,
     pub title: String,
+    #[serde(default)]
+    pub folder_id: Option<i64>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub settings: Option<ChatSettings>,
+}
+
+/// Per-chat overrides of the global model and mixer configuration.
+/// Unset fields fall back to the session arguments or global defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatSettings {
+    #[serde(default)]
+    pub profile_id: Option<i64>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<i64>,
+    #[serde(default)]
+    pub generation: Option<GenerationParams>,
+    #[serde(default)]
+    pub preset_id: Option<i64>,
+    #[serde(default)]
+    pub handle_x: Option<f64>,
+    #[serde(default)]
+    pub handle_y: Option<f64>,
+    /// Anchor label to weight, as last used in this chat.
+    #[serde(default)]
+    pub anchor_weights: Option<BTreeMap<String, f64>>,
+    /// Stable instructions placed before the mixed prompt.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    #[serde(default)]
+    pub system_messages: Option<SystemMessageHandling>,
+}
+
+/// What happens to `system` messages stored in a chat's history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemMessageHandling {
+    /// Fold them into the leading system prompt, after the chat's base prompt.
+    #[default]
+    Merge,
+    /// Send them where they are in the history.
+    Keep,
+    /// Leave them out.
+    Drop,
+}
+
+impl SystemMessageHandling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemMessageHandling::Merge => "merge",
+            SystemMessageHandling::Keep => "keep",
+            SystemMessageHandling::Drop => "drop",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "merge" => Some(SystemMessageHandling::Merge),
+            "keep" => Some(SystemMessageHandling::Keep),
+            "drop" => Some(SystemMessageHandling::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// How `merge_presets` handles anchors with the same label.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeConflictPolicy {
+    /// Keep the anchor from the earliest preset.
+    #[default]
+    First,
+    /// Keep the anchor from the latest preset.
+    Last,
+    /// Keep all of them, numbering the later labels.
+    Rename,
+}
+
+/// The preset and mixer position a chat was last used with.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatMixerState {
+    pub preset_id: Option<i64>,
+    pub handle_x: Option<f64>,
+    pub handle_y: Option<f64>,
+    /// Anchor label to weight.
+    #[serde(default)]
+    pub weights: BTreeMap<String, f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatWithPreview {
     pub id: i64,
     pub title: String,
+    pub folder_id: Option<i64>,
+    pub pinned: bool,
+    pub archived: bool,
+    pub tags: Vec<Tag>,
     pub created_at: String,
     pub updated_at: String,
     pub last_message: Option<String>,
     pub last_message_role: Option<String>,
+    pub message_count: i64,
+}
+
+/// Filters for `list_chats`. The default lists every non-archived chat.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChatFilter {
+    /// Only chats directly in this folder.
+    #[serde(default)]
+    pub folder_id: Option<i64>,
+    /// Only chats carrying all of these tags.
+    #[serde(default)]
+    pub tag_ids: Vec<i64>,
+    /// List archived chats instead of active ones.
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub pinned_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+}
+
+/// A soft-deleted chat, message or preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// "chat", "message" or "preset".
+    pub kind: String,
+    pub id: i64,
+    /// The chat a trashed message belongs to.
+    pub chat_id: Option<i64>,
+    /// Chat title, preset name or the start of the message.
+    pub title: String,
+    pub deleted_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +224,30 @@ pub struct Message {
     pub chat_id: i64,
     pub role: String,
     pub content: String,
+    pub reasoning: Option<String>,
+    pub created_at: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+/// A previous version of a message's content, saved when it was edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRevision {
+    pub id: i64,
+    pub message_id: i64,
+    pub content: String,
+    /// When this version was replaced.
+    pub created_at: String,
+}
+
+/// A file attached to a user message. The contents are fetched separately.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: i64,
+    pub message_id: i64,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
     pub created_at: String,
 }
 
@@ -82,6 +255,9 @@ pub struct Message {
 pub struct AnchorPreset {
     pub id: i64,
     pub name: String,
+    /// Ships with the app: cannot be deleted and can be reset to its factory anchors.
+    #[serde(default)]
+    pub is_builtin: bool,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -90,6 +266,9 @@ pub struct AnchorPreset {
 pub struct PresetAnchor {
     pub id: i64,
     pub preset_id: i64,
+    /// Library anchor this one was placed from, if any.
+    #[serde(default)]
+    pub anchor_id: Option<i64>,
     pub label: String,
     pub prompt: String,
     pub icon_small: String,
@@ -99,6 +278,302 @@ pub struct PresetAnchor {
     pub position_y: f64,
     pub influence_radius: f64,
     pub sort_order: i32,
+    #[serde(default)]
+    pub kind: AnchorKind,
+    /// Example exchanges of a `few_shot` anchor.
+    #[serde(default)]
+    pub examples: Vec<ExamplePair>,
+    /// Rules of a `constraint` anchor or words of a `vocabulary` anchor.
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+/// What an anchor contributes to generation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorKind {
+    /// Persona text blended into the mixed prompt.
+    #[default]
+    Persona,
+    /// Example exchanges sent as messages ahead of the history.
+    FewShot,
+    /// Rules appended to the system prompt word for word.
+    Constraint,
+    /// Words to favour, or to stay away from when the anchor is avoided.
+    Vocabulary,
+}
+
+impl AnchorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnchorKind::Persona => "persona",
+            AnchorKind::FewShot => "few_shot",
+            AnchorKind::Constraint => "constraint",
+            AnchorKind::Vocabulary => "vocabulary",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "persona" => Some(AnchorKind::Persona),
+            "few_shot" => Some(AnchorKind::FewShot),
+            "constraint" => Some(AnchorKind::Constraint),
+            "vocabulary" => Some(AnchorKind::Vocabulary),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExamplePair {
+    pub user: String,
+    pub assistant: String,
+}
+
+/// How anchor prompts are combined into the mixed prompt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MixStrategy {
+    /// Mix locally when at most one anchor steers toward something,
+    /// otherwise through the meta-prompt.
+    #[default]
+    Auto,
+    /// Always mix through the meta-prompt.
+    Llm,
+    /// Never call the LLM; active prompts are listed with their strengths.
+    Local,
+}
+
+/// Generation settings a preset applies while it is active. Unset fields
+/// fall back to the chat's settings; per-call arguments override them all.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PresetDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<i64>,
+    /// Replaces the built-in meta-prompt; must contain `{sources}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mix_strategy: Option<MixStrategy>,
+    /// Tokens per continuation request, used when no pacing is passed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_tokens: Option<i64>,
+}
+
+impl PresetDefaults {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err("Temperature must be between 0.0 and 2.0".to_string());
+            }
+        }
+        if let Some(p) = self.top_p {
+            if !(0.0..=1.0).contains(&p) {
+                return Err("Top P must be between 0.0 and 1.0".to_string());
+            }
+        }
+        if let Some(m) = self.max_tokens {
+            if m < 1 {
+                return Err("Max tokens must be at least 1".to_string());
+            }
+        }
+        if let Some(ref prompt) = self.meta_prompt {
+            if !prompt.contains("{sources}") {
+                return Err("Meta-prompt must contain the {sources} placeholder".to_string());
+            }
+        }
+        if let Some(c) = self.chunk_tokens {
+            if !(1..=256).contains(&c) {
+                return Err("Chunk size must be between 1 and 256 tokens".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rejected field of a preset write. `anchor_index` points into the
+/// submitted anchors; it is absent for preset-level fields like `name`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub anchor_index: Option<usize>,
+    pub message: String,
+}
+
+/// Error returned by `create_preset` and `update_preset`. Validation failures
+/// carry one entry per bad field; other failures only have a message.
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetWriteError {
+    pub message: String,
+    pub field_errors: Vec<FieldError>,
+}
+
+impl From<String> for PresetWriteError {
+    fn from(message: String) -> Self {
+        PresetWriteError {
+            message,
+            field_errors: Vec::new(),
+        }
+    }
+}
+
+/// An anchor in the shared library. Presets place it on their canvas with
+/// their own position, radius and sort order, and pick up edits to its text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryAnchor {
+    pub id: i64,
+    pub label: String,
+    pub prompt: String,
+    pub icon_small: String,
+    pub icon_large: String,
+    pub color: String,
+    #[serde(default)]
+    pub kind: AnchorKind,
+    /// Example exchanges of a `few_shot` anchor.
+    #[serde(default)]
+    pub examples: Vec<ExamplePair>,
+    /// Rules of a `constraint` anchor or words of a `vocabulary` anchor.
+    #[serde(default)]
+    pub items: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReasoningDelimiter {
+    pub start: String,
+    pub end: String,
+}
+
+/// Optional sampling parameters sent on top of temperature, top_p and max_tokens.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    /// Token id (as a string) to bias between -100 and 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<BTreeMap<String, f64>>,
+    /// Free-form fields merged into the request body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_body: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl GenerationParams {
+    /// Fields set in `self` win; everything else comes from `base`.
+    pub fn or(self, base: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            stop: self.stop.or_else(|| base.stop.clone()),
+            seed: self.seed.or(base.seed),
+            presence_penalty: self.presence_penalty.or(base.presence_penalty),
+            frequency_penalty: self.frequency_penalty.or(base.frequency_penalty),
+            logit_bias: self.logit_bias.or_else(|| base.logit_bias.clone()),
+            extra_body: self.extra_body.or_else(|| base.extra_body.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(ref stop) = self.stop {
+            if stop.iter().any(|s| s.is_empty()) {
+                return Err("Stop sequences cannot be empty".to_string());
+            }
+        }
+        for (name, value) in [
+            ("Presence penalty", self.presence_penalty),
+            ("Frequency penalty", self.frequency_penalty),
+        ] {
+            if let Some(v) = value {
+                if !(-2.0..=2.0).contains(&v) {
+                    return Err(format!("{} must be between -2.0 and 2.0", name));
+                }
+            }
+        }
+        if let Some(ref bias) = self.logit_bias {
+            for (token, v) in bias {
+                if token.parse::<u32>().is_err() {
+                    return Err(format!("Logit bias key '{}' is not a token id", token));
+                }
+                if !(-100.0..=100.0).contains(v) {
+                    return Err(format!("Logit bias for token {} must be between -100 and 100", token));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelProfile {
+    pub id: i64,
+    pub name: String,
+    pub model: String,
+    pub context_window: i64,
+    pub reasoning_delimiters: Vec<ReasoningDelimiter>,
+    pub reasoning_field: Option<String>,
+    pub generation: GenerationParams,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewModelProfile {
+    pub name: String,
+    pub model: String,
+    pub context_window: i64,
+    #[serde(default = "crate::reasoning::default_delimiters")]
+    pub reasoning_delimiters: Vec<ReasoningDelimiter>,
+    #[serde(default)]
+    pub reasoning_field: Option<String>,
+    #[serde(default)]
+    pub generation: GenerationParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmCall {
+    pub id: i64,
+    pub chat_id: Option<i64>,
+    pub session_id: Option<String>,
+    pub kind: String,
+    pub model: String,
+    /// "ok", "error" or "cancelled".
+    pub status: String,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub estimated: bool,
+    pub latency_ms: i64,
+    pub created_at: String,
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub key: String,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub estimated_calls: i64,
+    /// Calls that errored or were cancelled.
+    pub failed_calls: i64,
+    pub total_latency_ms: i64,
+    pub cost: f64,
+}
+
+/// Prices are in currency units per million tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub model: String,
+    pub prompt_price: f64,
+    pub completion_price: f64,
+    pub updated_at: String,
 }
 
 #[allow(dead_code)]
@@ -124,6 +599,10 @@ pub struct NewMessage {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewPresetAnchor {
+    /// When set, everything but position, radius and sort order is replaced
+    /// by this library anchor's.
+    #[serde(default)]
+    pub anchor_id: Option<i64>,
     pub label: String,
     pub prompt: String,
     pub icon_small: String,
@@ -133,4 +612,32 @@ pub struct NewPresetAnchor {
     pub position_y: f64,
     pub influence_radius: f64,
     pub sort_order: i32,
+    #[serde(default)]
+    pub kind: AnchorKind,
+    /// Example exchanges of a `few_shot` anchor.
+    #[serde(default)]
+    pub examples: Vec<ExamplePair>,
+    /// Rules of a `constraint` anchor or words of a `vocabulary` anchor.
+    #[serde(default)]
+    pub items: Vec<String>,
+}
+
+impl From<PresetAnchor> for NewPresetAnchor {
+    fn from(anchor: PresetAnchor) -> Self {
+        NewPresetAnchor {
+            anchor_id: anchor.anchor_id,
+            label: anchor.label,
+            prompt: anchor.prompt,
+            icon_small: anchor.icon_small,
+            icon_large: anchor.icon_large,
+            color: anchor.color,
+            position_x: anchor.position_x,
+            position_y: anchor.position_y,
+            influence_radius: anchor.influence_radius,
+            sort_order: anchor.sort_order,
+            kind: anchor.kind,
+            examples: anchor.examples,
+            items: anchor.items,
+        }
+    }
 }
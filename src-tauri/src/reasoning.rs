@@ -0,0 +1,132 @@
+use crate::models::ReasoningDelimiter;
+
+/// Message fields some providers use for hidden reasoning, in lookup order.
+pub const DEFAULT_REASONING_FIELDS: &[&str] = &["reasoning_content", "reasoning"];
+
+pub fn default_delimiters() -> Vec<ReasoningDelimiter> {
+    vec![ReasoningDelimiter {
+        start: "<think>".to_string(),
+        end: "</think>".to_string(),
+    }]
+}
+
+#[derive(Debug, Default)]
+pub struct FilteredChunk {
+    pub visible: String,
+    pub hidden: String,
+}
+
+/// Splits streamed text into visible output and reasoning blocks. Tags may
+/// arrive split across chunks (`<thi` + `nk>`), so a trailing partial tag is
+/// held back until the next chunk decides what it is.
+pub struct ReasoningFilter {
+    delimiters: Vec<ReasoningDelimiter>,
+    open: Option<usize>,
+    pending: String,
+    trim_next: bool,
+}
+
+/// Length of the longest suffix of `text` that is a proper prefix of a tag.
+fn partial_tag_len<'a>(text: &str, tags: impl Iterator<Item = &'a str>) -> usize {
+    let mut longest = 0;
+    for tag in tags {
+        for len in (1..tag.len()).rev() {
+            if len <= longest {
+                break;
+            }
+            if tag.is_char_boundary(len) && text.ends_with(&tag[..len]) {
+                longest = len;
+                break;
+            }
+        }
+    }
+    longest
+}
+
+impl ReasoningFilter {
+    pub fn new(delimiters: Vec<ReasoningDelimiter>) -> Self {
+        ReasoningFilter {
+            delimiters: delimiters
+                .into_iter()
+                .filter(|d| !d.start.is_empty() && !d.end.is_empty())
+                .collect(),
+            open: None,
+            pending: String::new(),
+            trim_next: false,
+        }
+    }
+
+    fn push_visible(&mut self, out: &mut FilteredChunk, text: &str) {
+        // Drop the whitespace a model usually leaves after closing a block,
+        // but not on every chunk (which would eat spaces between words)
+        let text = if self.trim_next { text.trim_start() } else { text };
+        if !text.is_empty() {
+            self.trim_next = false;
+        }
+        out.visible.push_str(text);
+    }
+
+    pub fn push(&mut self, chunk: &str) -> FilteredChunk {
+        let mut text = std::mem::take(&mut self.pending);
+        text.push_str(chunk);
+
+        let mut out = FilteredChunk::default();
+        let mut rest = text.as_str();
+
+        loop {
+            match self.open {
+                Some(i) => {
+                    let end_len = self.delimiters[i].end.len();
+                    if let Some(pos) = rest.find(self.delimiters[i].end.as_str()) {
+                        out.hidden.push_str(&rest[..pos]);
+                        rest = &rest[pos + end_len..];
+                        self.open = None;
+                        self.trim_next = true;
+                    } else {
+                        let keep = partial_tag_len(rest, std::iter::once(self.delimiters[i].end.as_str()));
+                        let split = rest.len() - keep;
+                        out.hidden.push_str(&rest[..split]);
+                        self.pending = rest[split..].to_string();
+                        break;
+                    }
+                }
+                None => {
+                    let next = self
+                        .delimiters
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, d)| rest.find(d.start.as_str()).map(|pos| (pos, i)))
+                        .min();
+
+                    if let Some((pos, i)) = next {
+                        let start_len = self.delimiters[i].start.len();
+                        self.push_visible(&mut out, &rest[..pos]);
+                        rest = &rest[pos + start_len..];
+                        self.open = Some(i);
+                    } else {
+                        let keep =
+                            partial_tag_len(rest, self.delimiters.iter().map(|d| d.start.as_str()));
+                        let split = rest.len() - keep;
+                        self.push_visible(&mut out, &rest[..split]);
+                        self.pending = rest[split..].to_string();
+                        break;
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Releases text held back as a possible partial tag at the end of a stream.
+    pub fn finish(&mut self) -> FilteredChunk {
+        let pending = std::mem::take(&mut self.pending);
+        let mut out = FilteredChunk::default();
+        if self.open.is_some() {
+            out.hidden = pending;
+        } else {
+            self.push_visible(&mut out, &pending);
+        }
+        out
+    }
+}
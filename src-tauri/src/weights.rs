@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::models::PresetAnchor;
+
+/// Radius of an anchor's icon on the canvas. A handle over the icon gives
+/// the anchor its full weight.
+pub const EMOTION_ICON_RADIUS: f64 = 15.0;
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+/// Canvas size the frontend assumes until it has measured the real one.
+pub const DEFAULT_CANVAS_WIDTH: f64 = 600.0;
+pub const DEFAULT_CANVAS_HEIGHT: f64 = 400.0;
+
+/// Distance of laid-out anchors from the center, as a share of the smaller
+/// half-side of the canvas.
+const PLACEMENT_RADIUS_FACTOR: f64 = 0.7;
+
+/// Influence radius the canvas gives anchors: 0.35 of the smaller side of the
+/// default canvas (its height).
+pub const DEFAULT_INFLUENCE_RADIUS: f64 = DEFAULT_CANVAS_HEIGHT * 0.35;
+
+/// Positions of `count` anchors spread evenly on a circle around the center
+/// of the default canvas, the first at the top. This is where the canvas puts
+/// the anchors of a preset that was never laid out.
+pub fn circle_layout(count: usize) -> Vec<(f64, f64)> {
+    let center_x = DEFAULT_CANVAS_WIDTH / 2.0;
+    let center_y = DEFAULT_CANVAS_HEIGHT / 2.0;
+    let placement_radius = center_x.min(center_y) * PLACEMENT_RADIUS_FACTOR;
+
+    (0..count)
+        .map(|i| {
+            let angle = (i as f64 / count as f64) * std::f64::consts::TAU - std::f64::consts::FRAC_PI_2;
+            (
+                center_x + placement_radius * angle.cos(),
+                center_y + placement_radius * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Whether a preset's anchors were never placed: they all sit at the origin.
+pub fn is_unplaced(positions: impl IntoIterator<Item = (f64, f64)>) -> bool {
+    let mut positions = positions.into_iter().peekable();
+    positions.peek().is_some() && positions.all(|(x, y)| x == 0.0 && y == 0.0)
+}
+
+// ---------------------------------------------------------------------------
+// Falloff curves
+// ---------------------------------------------------------------------------
+
+/// How an anchor's weight drops from 1 at its icon edge to 0 at its
+/// influence radius.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Falloff {
+    /// Straight line; what the canvas draws.
+    #[default]
+    Linear,
+    /// Eases out of the icon and into the edge.
+    Smoothstep,
+    /// Bell curve with the edge at three standard deviations, shifted to
+    /// reach 0 at the edge.
+    Gaussian,
+    /// Drops with the square of the distance, shifted to reach 0 at the edge.
+    InverseSquare,
+}
+
+/// Scaling applied to the weights of all anchors after falloff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Raw weights, each between 0 and 1.
+    #[default]
+    None,
+    /// Weights sum to 1.
+    Sum,
+    /// The strongest weight is 1.
+    Max,
+}
+
+/// Weight of one anchor whose center is `distance` away from the handle.
+/// A radius no bigger than the icon gives full weight over the icon and none
+/// anywhere else.
+pub fn falloff_weight(distance: f64, influence_radius: f64, falloff: Falloff) -> f64 {
+    if distance <= EMOTION_ICON_RADIUS {
+        return 1.0;
+    }
+    if distance >= influence_radius {
+        return 0.0;
+    }
+
+    // Inside the ring between icon and influence radius, so t is in (0, 1)
+    let t = (distance - EMOTION_ICON_RADIUS) / (influence_radius - EMOTION_ICON_RADIUS);
+
+    let weight = match falloff {
+        Falloff::Linear => 1.0 - t,
+        Falloff::Smoothstep => 1.0 - t * t * (3.0 - 2.0 * t),
+        Falloff::Gaussian => {
+            let sigma = 1.0 / 3.0;
+            let at = |t: f64| (-(t * t) / (2.0 * sigma * sigma)).exp();
+            let edge = at(1.0);
+            (at(t) - edge) / (1.0 - edge)
+        }
+        Falloff::InverseSquare => {
+            let at = |d: f64| (EMOTION_ICON_RADIUS / d).powi(2);
+            let edge = at(influence_radius);
+            (at(distance) - edge) / (1.0 - edge)
+        }
+    };
+
+    weight.clamp(0.0, 1.0)
+}
+
+/// Weights of every anchor, keyed by label, for a handle at
+/// (`handle_x`, `handle_y`).
+pub fn compute_weights(
+    anchors: &[PresetAnchor],
+    handle_x: f64,
+    handle_y: f64,
+    falloff: Falloff,
+    normalization: Normalization,
+) -> BTreeMap<String, f64> {
+    let mut weights: BTreeMap<String, f64> = anchors
+        .iter()
+        .map(|a| {
+            let distance = (handle_x - a.position_x).hypot(handle_y - a.position_y);
+            (
+                a.label.clone(),
+                falloff_weight(distance, a.influence_radius, falloff),
+            )
+        })
+        .collect();
+
+    let divisor = match normalization {
+        Normalization::None => return weights,
+        Normalization::Sum => weights.values().sum::<f64>(),
+        Normalization::Max => weights.values().copied().fold(0.0, f64::max),
+    };
+
+    if divisor > 0.0 {
+        for weight in weights.values_mut() {
+            *weight /= divisor;
+        }
+    }
+
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [Falloff; 4] = [
+        Falloff::Linear,
+        Falloff::Smoothstep,
+        Falloff::Gaussian,
+        Falloff::InverseSquare,
+    ];
+
+    fn anchor(label: &str, x: f64, y: f64, influence_radius: f64) -> PresetAnchor {
+        PresetAnchor {
+            id: 0,
+            preset_id: 0,
+            anchor_id: None,
+            label: label.to_string(),
+            prompt: String::new(),
+            icon_small: String::new(),
+            icon_large: String::new(),
+            color: "#000000".to_string(),
+            position_x: x,
+            position_y: y,
+            influence_radius,
+            sort_order: 0,
+            kind: Default::default(),
+            examples: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn every_curve_is_full_on_the_icon_and_empty_from_the_radius() {
+        for falloff in CURVES {
+            assert_eq!(falloff_weight(0.0, 140.0, falloff), 1.0, "{:?}", falloff);
+            assert_eq!(falloff_weight(EMOTION_ICON_RADIUS, 140.0, falloff), 1.0, "{:?}", falloff);
+            assert_eq!(falloff_weight(140.0, 140.0, falloff), 0.0, "{:?}", falloff);
+            assert_eq!(falloff_weight(500.0, 140.0, falloff), 0.0, "{:?}", falloff);
+        }
+    }
+
+    #[test]
+    fn every_curve_falls_steadily_inside_the_ring() {
+        for falloff in CURVES {
+            let mut previous = 1.0;
+            for step in 1..100 {
+                let distance = EMOTION_ICON_RADIUS + (140.0 - EMOTION_ICON_RADIUS) * step as f64 / 100.0;
+                let weight = falloff_weight(distance, 140.0, falloff);
+                assert!(weight > 0.0 && weight < previous, "{:?} at {}", falloff, distance);
+                previous = weight;
+            }
+            // Close to the edge the weight is close to 0, with no jump at the radius
+            assert!(falloff_weight(139.9, 140.0, falloff) < 0.01, "{:?}", falloff);
+        }
+    }
+
+    #[test]
+    fn a_radius_inside_the_icon_only_weights_the_icon() {
+        for falloff in CURVES {
+            assert_eq!(falloff_weight(0.0, 0.35, falloff), 1.0);
+            assert_eq!(falloff_weight(EMOTION_ICON_RADIUS, 0.35, falloff), 1.0);
+            assert_eq!(falloff_weight(EMOTION_ICON_RADIUS + 0.1, 0.35, falloff), 0.0);
+        }
+    }
+
+    /// Mirrors `calculateEmotionValues` in src/lib/canvas/anchors.ts, which
+    /// the canvas uses; the linear curve must agree with it.
+    fn canvas_value(distance: f64, influence_radius: f64) -> f64 {
+        if distance <= EMOTION_ICON_RADIUS {
+            1.0
+        } else if distance <= influence_radius {
+            let normalized =
+                (distance - EMOTION_ICON_RADIUS) / (influence_radius - EMOTION_ICON_RADIUS);
+            (1.0 - normalized).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    #[test]
+    fn linear_matches_the_canvas() {
+        let anchors = [
+            anchor("Happiness", 300.0, 60.0, DEFAULT_INFLUENCE_RADIUS),
+            anchor("Anger", 440.0, 200.0, DEFAULT_INFLUENCE_RADIUS),
+            anchor("Calm", 300.0, 200.0, 80.0),
+        ];
+        for (hx, hy) in [(300.0, 200.0), (350.0, 120.0), (420.0, 210.0), (10.0, 10.0)] {
+            let weights = compute_weights(&anchors, hx, hy, Falloff::Linear, Normalization::None);
+            for a in &anchors {
+                let distance = (hx - a.position_x).hypot(hy - a.position_y);
+                let expected = canvas_value(distance, a.influence_radius);
+                assert!((weights[&a.label] - expected).abs() < 1e-12, "{} at {},{}", a.label, hx, hy);
+            }
+        }
+
+        // Halfway through the ring is exactly half
+        assert_eq!(falloff_weight(EMOTION_ICON_RADIUS + 62.5, 140.0, Falloff::Linear), 0.5);
+    }
+
+    #[test]
+    fn normalization_scales_all_weights() {
+        let anchors = [anchor("A", 100.0, 100.0, 140.0), anchor("B", 200.0, 100.0, 140.0)];
+        let (hx, hy) = (140.0, 100.0);
+
+        let raw = compute_weights(&anchors, hx, hy, Falloff::Linear, Normalization::None);
+        let sum = compute_weights(&anchors, hx, hy, Falloff::Linear, Normalization::Sum);
+        let max = compute_weights(&anchors, hx, hy, Falloff::Linear, Normalization::Max);
+
+        assert!((sum.values().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((max["A"] - 1.0).abs() < 1e-12);
+        assert!((max["B"] - raw["B"] / raw["A"]).abs() < 1e-12);
+        assert!((sum["A"] / sum["B"] - raw["A"] / raw["B"]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn normalizing_all_zero_weights_leaves_them_zero() {
+        let anchors = [anchor("A", 100.0, 100.0, 140.0)];
+        for normalization in [Normalization::Sum, Normalization::Max] {
+            let weights = compute_weights(&anchors, 500.0, 500.0, Falloff::Linear, normalization);
+            assert_eq!(weights["A"], 0.0);
+        }
+    }
+
+    #[test]
+    fn the_circle_layout_starts_at_the_top() {
+        let positions = circle_layout(4);
+        let expected = [(300.0, 60.0), (440.0, 200.0), (300.0, 340.0), (160.0, 200.0)];
+        for ((x, y), (ex, ey)) in positions.into_iter().zip(expected) {
+            assert!((x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9);
+        }
+        assert!(is_unplaced([(0.0, 0.0), (0.0, 0.0)]));
+        assert!(!is_unplaced([(0.0, 0.0), (1.0, 0.0)]));
+        assert!(!is_unplaced(std::iter::empty()));
+    }
+}
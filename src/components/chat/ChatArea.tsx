@@ -137,6 +137,8 @@ export function ChatArea() {
                     chat_id: activeChatId,
                     role: 'assistant',
                     content: streamingContent,
+                    reasoning: null,
+                    attachments: [],
                     created_at: new Date().toISOString(),
                   }}
                   isStreaming
@@ -7,13 +7,14 @@ import { listen } from '@tauri-apps/api/event';
 
 export function ChatInput() {
   const textareaRef = useRef<HTMLTextAreaElement>(null);
-  const { activeChatId, isStreaming, addMessage, createChat, appendStreamingContent, finalizeStreaming, setIsStreaming, clearStreaming, pendingRegenerate, setPendingRegenerate, setError, clearError, inputValue, setInputValue } = useChatStore();
+  const { activeChatId, isStreaming, addMessage, createChat, appendStreamingContent, appendStreamingReasoning, finalizeStreaming, setIsStreaming, clearStreaming, pendingRegenerate, setPendingRegenerate, setError, clearError, inputValue, setInputValue } = useChatStore();
   const input = inputValue;
   const setInput = setInputValue;
   const config = useSettingsStore(s => s.config);
   const getWeightedAnchors = useMixerStore(s => s.getWeightedAnchors);
   const setStatus = useMixerStore(s => s.setStatus);
   const speed = useMixerStore(s => s.speed);
+  const activePresetId = useMixerStore(s => s.activePresetId);
 
   // Auto-resize textarea
   useLayoutEffect(() => {
@@ -31,10 +32,15 @@ export function ChatInput() {
       appendStreamingContent(event.payload.text);
     });
 
+    const unlistenReasoning = await listen<{ text: string }>('llm:reasoning', (event) => {
+      appendStreamingReasoning(event.payload.text);
+    });
+
     const unlistenComplete = await listen<{ status: string }>('llm:complete', async () => {
       await finalizeStreaming();
       setStatus('Done');
       unlistenToken();
+      unlistenReasoning();
       unlistenComplete();
       unlistenStatus();
       unlistenError();
@@ -53,6 +59,7 @@ export function ChatInput() {
       });
       clearStreaming();
       unlistenToken();
+      unlistenReasoning();
       unlistenComplete();
       unlistenStatus();
       unlistenError();
@@ -64,6 +71,9 @@ export function ChatInput() {
       // If no anchors have weight, use neutral
       const effectiveAnchors = anchors.length > 0 ? anchors : [{ label: 'Neutral', prompt: 'You are balanced, calm, and objective. Respond without strong emotional coloring. Be clear, direct, and informative. Maintain a professional, even-tempered tone. Provide thoughtful, measured responses without dramatic flair.', weight: 1.0 }];
 
+      // Temperature, max tokens and top-p are resolved by the backend from
+      // the active preset, the chat and the global settings
+      const { x: handleX, y: handleY } = useMixerStore.getState().handlePos;
       await api.startMixingSession({
         chatId,
         anchors: effectiveAnchors,
@@ -71,9 +81,9 @@ export function ChatInput() {
         apiKey: config.apiKey,
         model: config.model,
         speed: speed,
-        temperature: config.temperature,
-        maxTokens: config.maxTokens,
-        topP: config.topP,
+        presetId: activePresetId ?? undefined,
+        handleX,
+        handleY,
       });
     } catch (e) {
       console.error('Failed to start mixing session:', e);
@@ -83,6 +93,7 @@ export function ChatInput() {
         type: 'error',
       });
       unlistenToken();
+      unlistenReasoning();
       unlistenComplete();
       unlistenStatus();
       unlistenError();
@@ -2,6 +2,7 @@ import { useRef, useEffect, useCallback, useMemo } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { useMixerStore } from '../../stores/mixerStore';
 import { useSettingsStore } from '../../stores/settingsStore';
+import { useChatStore } from '../../stores/chatStore';
 import { CANVAS_CONSTANTS, DEFAULT_ANCHORS } from '../../lib/constants';
 import { lerp, calculateDistance, debounce } from '../../lib/utils';
 import { setupAnchors, calculateEmotionValues } from '../../lib/canvas/anchors';
@@ -67,17 +68,29 @@ export function MixerCanvas() {
   const performUpdateWeights = useCallback(async (values: Record<string, number>) => {
     const weighted = anchorsRef.current
       .filter(a => (values[a.name] || 0) > 0)
-      .map(a => ({ label: a.name, prompt: a.prompt, weight: values[a.name] || 0 }));
+      .map(a => ({
+        label: a.name,
+        prompt: a.prompt,
+        weight: values[a.name] || 0,
+        kind: a.kind,
+        examples: a.examples,
+        items: a.items,
+      }));
 
     if (weighted.length > 0 && config.providerUrl) {
-      const { setStatus, setConnectionStatus } = useMixerStore.getState();
+      const { setStatus, setConnectionStatus, activePresetId } = useMixerStore.getState();
+      const { x, y } = animStateRef.current.handlePos;
 
       try {
         await api.updateWeights({
+          chatId: useChatStore.getState().activeChatId,
           anchors: weighted,
           providerUrl: config.providerUrl,
           apiKey: config.apiKey,
           model: config.model,
+          presetId: activePresetId,
+          handleX: x,
+          handleY: y,
         });
         // Success
         setStatus('Done');
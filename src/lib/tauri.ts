@@ -1,18 +1,53 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Chat, ChatWithPreview, Message, AnchorPreset, PresetAnchor, NewPresetAnchor, WeightedAnchorInput } from './types';
+import type { Attachment, MessageRevision, Chat, ChatFilter, SystemMessageHandling, TrashItem, TrashItemKind, ChatMixerState, Folder, Tag, ChatSettings, ChatWithPreview, Message, AnchorPreset, AnchorKind, ExamplePair, LibraryAnchor, MergeConflictPolicy, Falloff, WeightNormalization, PresetAnchor, NewPresetAnchor, WeightedAnchorInput, PresetDefaults, ModelProfile, NewModelProfile, GenerationParams, HistoryStrategy, LlmCall, UsageGrouping, UsageSummary, ModelPrice, PacingConfig } from './types';
 
 // Chat commands
 export const createChat = (title: string) => invoke<Chat>('create_chat', { title });
-export const listChats = () => invoke<ChatWithPreview[]>('list_chats');
+export const listChats = (filter?: ChatFilter) => invoke<ChatWithPreview[]>('list_chats', { filter });
 export const getChat = (chatId: number) => invoke<Chat>('get_chat', { chatId });
 export const deleteChat = (chatId: number) => invoke<void>('delete_chat', { chatId });
+export const forkChat = (chatId: number, upToMessageId: number, newTitle?: string) => invoke<Chat>('fork_chat', { chatId, upToMessageId, newTitle });
+export const duplicateChat = (chatId: number, newTitle?: string) => invoke<Chat>('duplicate_chat', { chatId, newTitle });
 export const updateChatTitle = (chatId: number, title: string) => invoke<void>('update_chat_title', { chatId, title });
+export const getChatSettings = (chatId: number) => invoke<ChatSettings | null>('get_chat_settings', { chatId });
+export const setChatSettings = (chatId: number, settings: ChatSettings) => invoke<void>('set_chat_settings', { chatId, settings });
+export const setChatSystemPrompt = (chatId: number, systemPrompt: string | null, systemMessages?: SystemMessageHandling) => invoke<void>('set_chat_system_prompt', { chatId, systemPrompt, systemMessages });
+export const getChatMixerState = (chatId: number) => invoke<ChatMixerState | null>('get_chat_mixer_state', { chatId });
+export const setChatMixerState = (chatId: number, state: ChatMixerState) => invoke<void>('set_chat_mixer_state', { chatId, state });
+export const setChatPinned = (chatId: number, pinned: boolean) => invoke<void>('set_chat_pinned', { chatId, pinned });
+export const setChatArchived = (chatId: number, archived: boolean) => invoke<void>('set_chat_archived', { chatId, archived });
+export const moveChatToFolder = (chatId: number, folderId: number | null) => invoke<void>('move_chat_to_folder', { chatId, folderId });
+
+// Folder and tag commands
+export const listFolders = () => invoke<Folder[]>('list_folders');
+export const createFolder = (name: string, parentId?: number | null) => invoke<Folder>('create_folder', { name, parentId });
+export const updateFolder = (folderId: number, name: string, parentId: number | null) => invoke<void>('update_folder', { folderId, name, parentId });
+export const deleteFolder = (folderId: number) => invoke<void>('delete_folder', { folderId });
+export const listTags = () => invoke<Tag[]>('list_tags');
+export const createTag = (name: string, color?: string | null) => invoke<Tag>('create_tag', { name, color });
+export const updateTag = (tagId: number, name: string, color: string | null) => invoke<void>('update_tag', { tagId, name, color });
+export const deleteTag = (tagId: number) => invoke<void>('delete_tag', { tagId });
+export const setChatTags = (chatId: number, tagIds: number[]) => invoke<void>('set_chat_tags', { chatId, tagIds });
+
+// Trash commands
+export const listTrash = () => invoke<TrashItem[]>('list_trash');
+export const restoreTrashItem = (kind: TrashItemKind, id: number) => invoke<void>('restore_trash_item', { kind, id });
+export const purgeTrashItem = (kind: TrashItemKind, id: number) => invoke<void>('purge_trash_item', { kind, id });
+export const emptyTrash = () => invoke<void>('empty_trash');
+export const getTrashRetentionDays = () => invoke<number>('get_trash_retention_days');
+export const setTrashRetentionDays = (days: number) => invoke<void>('set_trash_retention_days', { days });
 
 // Message commands
-export const getMessages = (chatId: number) => invoke<Message[]>('get_messages', { chatId });
-export const addMessage = (chatId: number, role: string, content: string) => invoke<Message>('add_message', { chatId, role, content });
+export const getMessages = (chatId: number, page?: { beforeId?: number; limit?: number }) => invoke<Message[]>('get_messages', { chatId, ...page });
+export const getMessageIds = (chatId: number) => invoke<number[]>('get_message_ids', { chatId });
+export const addMessage = (chatId: number, role: string, content: string, reasoning?: string) => invoke<Message>('add_message', { chatId, role, content, reasoning });
 export const deleteMessagesAfter = (chatId: number, messageId: number) => invoke<void>('delete_messages_after', { chatId, messageId });
 export const updateMessage = (messageId: number, content: string) => invoke<void>('update_message', { messageId, content });
+export const listMessageRevisions = (messageId: number) => invoke<MessageRevision[]>('list_message_revisions', { messageId });
+export const restoreMessageRevision = (revisionId: number) => invoke<void>('restore_message_revision', { revisionId });
+export const addAttachment = (messageId: number, fileName: string, mimeType: string, data: Uint8Array) => invoke<Attachment>('add_attachment', { messageId, fileName, mimeType, data: Array.from(data) });
+export const getAttachmentData = (attachmentId: number) => invoke<number[]>('get_attachment_data', { attachmentId });
+export const deleteAttachment = (attachmentId: number) => invoke<void>('delete_attachment', { attachmentId });
 
 // Preset commands
 export const listPresets = () => invoke<AnchorPreset[]>('list_presets');
@@ -20,33 +55,128 @@ export const createPreset = (name: string, anchors: NewPresetAnchor[]) => invoke
 export const updatePreset = (presetId: number, name: string, anchors: NewPresetAnchor[]) => invoke<void>('update_preset', { presetId, name, anchors });
 export const renamePreset = (presetId: number, name: string) => invoke<void>('rename_preset', { presetId, name });
 export const deletePreset = (presetId: number) => invoke<void>('delete_preset', { presetId });
+export const resetBuiltinPreset = (presetId: number) => invoke<void>('reset_builtin_preset', { presetId });
+export const cloneBuiltinPreset = (presetId: number, newName?: string) =>
+  invoke<AnchorPreset>('clone_builtin_preset', { presetId, newName });
+export const duplicatePreset = (presetId: number, newName: string) =>
+  invoke<AnchorPreset>('duplicate_preset', { presetId, newName });
+export const mergePresets = (presetIds: number[], newName: string, conflictPolicy?: MergeConflictPolicy) =>
+  invoke<AnchorPreset>('merge_presets', { presetIds, newName, conflictPolicy });
 export const getPresetAnchors = (presetId: number) => invoke<PresetAnchor[]>('get_preset_anchors', { presetId });
+export const computeWeights = (params: {
+  presetId: number;
+  handleX: number;
+  handleY: number;
+  falloff?: Falloff;
+  normalization?: WeightNormalization;
+}) => invoke<Record<string, number>>('compute_weights', params);
+export const getPresetDefaults = (presetId: number) => invoke<PresetDefaults | null>('get_preset_defaults', { presetId });
+export const setPresetDefaults = (presetId: number, defaults: PresetDefaults | null) =>
+  invoke<void>('set_preset_defaults', { presetId, defaults });
+
+// Anchor library commands
+export const listAnchors = () => invoke<LibraryAnchor[]>('list_anchors');
+type AnchorFields = {
+  label: string;
+  prompt: string;
+  iconSmall: string;
+  iconLarge: string;
+  color: string;
+  kind?: AnchorKind;
+  examples?: ExamplePair[];
+  items?: string[];
+};
+export const createAnchor = (anchor: AnchorFields) =>
+  invoke<LibraryAnchor>('create_anchor', anchor);
+export const updateAnchor = (anchorId: number, anchor: AnchorFields) =>
+  invoke<void>('update_anchor', { anchorId, ...anchor });
+export const deleteAnchor = (anchorId: number) => invoke<void>('delete_anchor', { anchorId });
+export const getAnchorPresets = (anchorId: number) => invoke<AnchorPreset[]>('get_anchor_presets', { anchorId });
+export const addPresetAnchorToLibrary = (presetAnchorId: number) =>
+  invoke<LibraryAnchor>('add_preset_anchor_to_library', { presetAnchorId });
+
+// Profile commands
+export const listProfiles = () => invoke<ModelProfile[]>('list_profiles');
+export const createProfile = (profile: NewModelProfile) => invoke<ModelProfile>('create_profile', { profile });
+export const updateProfile = (profileId: number, profile: NewModelProfile) => invoke<void>('update_profile', { profileId, profile });
+export const deleteProfile = (profileId: number) => invoke<void>('delete_profile', { profileId });
 
 // Settings commands
 export const getSetting = (key: string) => invoke<string | null>('get_setting', { key });
 export const setSetting = (key: string, value: string) => invoke<void>('set_setting', { key, value });
 export const fetchModels = (providerUrl: string, apiKey: string) => invoke<string[]>('fetch_models', { providerUrl, apiKey });
+export const listModelPrices = () => invoke<ModelPrice[]>('list_model_prices');
+export const setModelPrice = (model: string, promptPrice: number, completionPrice: number) => invoke<void>('set_model_price', { model, promptPrice, completionPrice });
+export const deleteModelPrice = (model: string) => invoke<void>('delete_model_price', { model });
+
+// Usage commands
+export const listLlmCalls = (chatId?: number, limit?: number) => invoke<LlmCall[]>('list_llm_calls', { chatId, limit });
+export const getUsageSummary = (groupBy: UsageGrouping, filters: { chatId?: number; since?: string; until?: string } = {}) =>
+  invoke<UsageSummary[]>('get_usage_summary', { groupBy, ...filters });
 
 // LLM commands
 export const startMixingSession = (params: {
   chatId: number;
-  anchors: WeightedAnchorInput[];
+  anchors?: WeightedAnchorInput[];
   providerUrl: string;
   apiKey: string;
-  model: string;
-  speed: string;
-  temperature: number;
-  maxTokens: number;
-  topP: number;
+  model?: string;
+  /** Ignored when pacing is given */
+  speed?: string;
+  /** Omitted settings come from the chat, then the preset, then global settings (a newly picked preset comes before the chat); the resolved values are saved on the chat */
+  temperature?: number;
+  maxTokens?: number;
+  topP?: number;
+  profileId?: number;
+  historyStrategy?: HistoryStrategy;
+  pacing?: PacingConfig;
+  generation?: GenerationParams;
+  presetId?: number;
+  /** Handle position the anchors' weights were read at, saved with them */
+  handleX?: number;
+  handleY?: number;
 }) => invoke<void>('start_mixing_session', params);
 
+export const continueMessage = (params: {
+  messageId: number;
+  anchors?: WeightedAnchorInput[];
+  providerUrl: string;
+  apiKey: string;
+  /** Ignored when pacing is given */
+  speed?: string;
+  pacing?: PacingConfig;
+  /** Handle position the anchors' weights were read at, saved with them */
+  handleX?: number;
+  handleY?: number;
+}) => invoke<void>('continue_message', params);
+
 export const updateWeights = (params: {
+  /** Chat whose mixer state is saved, and whose running session is remixed */
+  chatId: number | null;
   anchors: WeightedAnchorInput[];
   providerUrl: string;
   apiKey: string;
   model: string;
+  presetId?: number | null;
+  handleX?: number;
+  handleY?: number;
 }) => invoke<void>('update_weights', params);
 
 export const togglePause = () => invoke<boolean>('toggle_pause');
 export const setSpeed = (speed: string) => invoke<void>('set_speed', { speed });
+export const setPacing = (pacing: PacingConfig) => invoke<void>('set_pacing', { pacing });
 export const cancelSession = () => invoke<void>('cancel_session');
+export const generateChatTitle = (params: {
+  chatId: number;
+  providerUrl: string;
+  apiKey: string;
+  model?: string;
+}) => invoke<string>('generate_chat_title', params);
+export const generatePreset = (params: {
+  description: string;
+  count: number;
+  name?: string;
+  providerUrl: string;
+  apiKey: string;
+  model?: string;
+}) => invoke<AnchorPreset>('generate_preset', params);
@@ -1,17 +1,82 @@
 export interface Chat {
   id: number;
   title: string;
+  folder_id: number | null;
+  pinned: boolean;
+  archived: boolean;
+  tags: Tag[];
   created_at: string;
   updated_at: string;
+  settings?: ChatSettings | null;
+}
+
+export interface ChatSettings {
+  profile_id?: number | null;
+  model?: string | null;
+  temperature?: number | null;
+  top_p?: number | null;
+  max_tokens?: number | null;
+  generation?: GenerationParams | null;
+  preset_id?: number | null;
+  handle_x?: number | null;
+  handle_y?: number | null;
+  anchor_weights?: Record<string, number> | null;
+  system_prompt?: string | null;
+  system_messages?: SystemMessageHandling | null;
+}
+
+export type SystemMessageHandling = 'merge' | 'keep' | 'drop';
+
+export interface ChatFilter {
+  folder_id?: number | null;
+  tag_ids?: number[];
+  archived?: boolean;
+  pinned_only?: boolean;
+}
+
+export interface Folder {
+  id: number;
+  name: string;
+  parent_id: number | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  color: string | null;
+}
+
+export type TrashItemKind = 'chat' | 'message' | 'preset';
+
+export interface TrashItem {
+  kind: TrashItemKind;
+  id: number;
+  chat_id: number | null;
+  title: string;
+  deleted_at: string;
+}
+
+export interface ChatMixerState {
+  preset_id: number | null;
+  handle_x: number | null;
+  handle_y: number | null;
+  weights: Record<string, number>;
 }
 
 export interface ChatWithPreview {
   id: number;
   title: string;
+  folder_id: number | null;
+  pinned: boolean;
+  archived: boolean;
+  tags: Tag[];
   created_at: string;
   updated_at: string;
   last_message: string | null;
   last_message_role: string | null;
+  message_count: number;
 }
 
 export interface Message {
@@ -19,19 +84,47 @@ export interface Message {
   chat_id: number;
   role: 'user' | 'assistant' | 'system';
   content: string;
+  reasoning: string | null;
+  created_at: string;
+  attachments: Attachment[];
+}
+
+export interface MessageRevision {
+  id: number;
+  message_id: number;
+  content: string;
+  created_at: string;
+}
+
+export interface Attachment {
+  id: number;
+  message_id: number;
+  file_name: string;
+  mime_type: string;
+  size_bytes: number;
   created_at: string;
 }
 
 export interface AnchorPreset {
   id: number;
   name: string;
+  is_builtin: boolean;
   created_at: string;
   updated_at: string;
 }
 
+/** persona prompts are blended; the other kinds are applied directly */
+export type AnchorKind = 'persona' | 'few_shot' | 'constraint' | 'vocabulary';
+
+export interface ExamplePair {
+  user: string;
+  assistant: string;
+}
+
 export interface PresetAnchor {
   id: number;
   preset_id: number;
+  anchor_id: number | null;
   label: string;
   prompt: string;
   icon_small: string;
@@ -41,9 +134,62 @@ export interface PresetAnchor {
   position_y: number;
   influence_radius: number;
   sort_order: number;
+  kind: AnchorKind;
+  examples: ExamplePair[];
+  items: string[];
+}
+
+/** Curve from full weight at an anchor's icon to zero at its influence radius */
+export type Falloff = 'linear' | 'smoothstep' | 'gaussian' | 'inverse_square';
+
+/** Scaling applied to all weights after falloff */
+export type WeightNormalization = 'none' | 'sum' | 'max';
+
+/** How anchor prompts become the mixed prompt; 'auto' mixes locally when it can */
+export type MixStrategy = 'auto' | 'llm' | 'local';
+
+/** Generation settings applied while a preset is active */
+export interface PresetDefaults {
+  temperature?: number;
+  top_p?: number;
+  max_tokens?: number;
+  /** Must contain {sources} */
+  meta_prompt?: string;
+  mix_strategy?: MixStrategy;
+  chunk_tokens?: number;
+}
+
+/** How mergePresets resolves anchors that share a label */
+export type MergeConflictPolicy = 'first' | 'last' | 'rename';
+
+export interface FieldError {
+  field: string;
+  anchor_index: number | null;
+  message: string;
+}
+
+/** Rejection value of createPreset and updatePreset */
+export interface PresetWriteError {
+  message: string;
+  field_errors: FieldError[];
+}
+
+export interface LibraryAnchor {
+  id: number;
+  label: string;
+  prompt: string;
+  icon_small: string;
+  icon_large: string;
+  color: string;
+  kind: AnchorKind;
+  examples: ExamplePair[];
+  items: string[];
+  created_at: string;
+  updated_at: string;
 }
 
 export interface NewPresetAnchor {
+  anchor_id?: number | null;
   label: string;
   prompt: string;
   icon_small: string;
@@ -53,6 +199,86 @@ export interface NewPresetAnchor {
   position_y: number;
   influence_radius: number;
   sort_order: number;
+  kind?: AnchorKind;
+  examples?: ExamplePair[];
+  items?: string[];
+}
+
+export interface ReasoningDelimiter {
+  start: string;
+  end: string;
+}
+
+export interface GenerationParams {
+  stop?: string[];
+  seed?: number;
+  presence_penalty?: number;
+  frequency_penalty?: number;
+  logit_bias?: Record<string, number>;
+  extra_body?: Record<string, unknown>;
+}
+
+export interface ModelProfile {
+  id: number;
+  name: string;
+  model: string;
+  context_window: number;
+  reasoning_delimiters: ReasoningDelimiter[];
+  reasoning_field: string | null;
+  generation: GenerationParams;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface NewModelProfile {
+  name: string;
+  model: string;
+  context_window: number;
+  reasoning_delimiters?: ReasoningDelimiter[];
+  reasoning_field?: string | null;
+  generation?: GenerationParams;
+}
+
+export type HistoryStrategy =
+  | { type: 'full' }
+  | { type: 'sliding_window' }
+  | { type: 'keep_first_last'; keep_first: number; keep_last: number }
+  | { type: 'summarize' };
+
+export interface LlmCall {
+  id: number;
+  chat_id: number | null;
+  session_id: string | null;
+  kind: 'mix' | 'continuation' | 'summary' | 'title' | 'preset';
+  model: string;
+  status: 'ok' | 'error' | 'cancelled';
+  prompt_tokens: number;
+  completion_tokens: number;
+  estimated: boolean;
+  latency_ms: number;
+  created_at: string;
+  cost: number;
+}
+
+export type UsageGrouping = 'chat' | 'session' | 'day' | 'model' | 'kind';
+
+export interface UsageSummary {
+  key: string;
+  calls: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  estimated_calls: number;
+  /** Calls that errored or were cancelled */
+  failed_calls: number;
+  total_latency_ms: number;
+  cost: number;
+}
+
+export interface ModelPrice {
+  model: string;
+  prompt_price: number;
+  completion_price: number;
+  updated_at: string;
 }
 
 export interface Anchor {
@@ -69,12 +295,22 @@ export interface Anchor {
   displayX: number;
   displayY: number;
   D_influence: number;
+  /** Library anchor this one was placed from, if any */
+  anchorId?: number | null;
+  kind?: AnchorKind;
+  examples?: ExamplePair[];
+  items?: string[];
 }
 
 export interface WeightedAnchorInput {
   label: string;
   prompt: string;
+  /** -1..1; a negative weight avoids the anchor */
   weight: number;
+  polarity?: 'toward' | 'avoid';
+  kind?: AnchorKind;
+  examples?: ExamplePair[];
+  items?: string[];
 }
 
 export interface LLMConfig {
@@ -86,6 +322,14 @@ export interface LLMConfig {
   topP: number;
 }
 
+export interface PacingConfig {
+  chunk_tokens?: number;
+  delay_ms?: number;
+  tokens_per_second?: number;
+  remix_slowdown_ms?: number;
+  remix_slowdown_steps?: number;
+}
+
 export interface EmotionValues {
   [name: string]: number;
 }
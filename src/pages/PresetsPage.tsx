@@ -4,15 +4,25 @@ import { useMixerStore } from '../stores/mixerStore';
 import { showToast } from '../components/ui/Toast';
 import { IconRenderer } from '../components/ui/IconRenderer';
 import { AnchorForm, AnchorFormData, EMPTY_FORM } from '../components/mixer/AnchorForm';
-import type { AnchorPreset } from '../lib/types';
+import type { AnchorPreset, PresetWriteError } from '../lib/types';
 import '../styles/mixer.css';
 
+/** First field error of a rejected preset write, or `fallback` */
+function presetErrorMessage(e: unknown, fallback: string): string {
+  const error = e as Partial<PresetWriteError> | null;
+  const field = error?.field_errors?.[0];
+  if (field) {
+    return field.anchor_index !== null ? `Anchor ${field.anchor_index + 1}: ${field.message}` : field.message;
+  }
+  return error?.message ?? fallback;
+}
+
 export function PresetsPage() {
   const navigate = useNavigate();
   const {
     presets, anchors, activePresetId,
     loadPresets, loadPresetAnchors, savePreset, deletePreset,
-    renamePreset, duplicatePreset, saveCurrentToPreset,
+    renamePreset, duplicatePreset, resetBuiltinPreset, saveCurrentToPreset,
     addAnchor, updateAnchor, removeAnchor, resetPositions,
     createNewPreset,
   } = useMixerStore();
@@ -37,7 +47,7 @@ export function PresetsPage() {
       showToast({ message: 'Preset saved successfully', type: 'success' });
     } catch (e) {
       console.error(e);
-      showToast({ message: 'Failed to save preset', type: 'error' });
+      showToast({ message: presetErrorMessage(e, 'Failed to save preset'), type: 'error' });
     }
   };
 
@@ -47,7 +57,7 @@ export function PresetsPage() {
       showToast({ message: 'Changes saved to current preset', type: 'success' });
     } catch (e) {
       console.error(e);
-      showToast({ message: 'Failed to save changes', type: 'error' });
+      showToast({ message: presetErrorMessage(e, 'Failed to save changes'), type: 'error' });
     }
   };
 
@@ -64,9 +74,14 @@ export function PresetsPage() {
 
   const handleFinishRename = async () => {
     if (renamingId !== null && renameValue.trim()) {
-      await renamePreset(renamingId, renameValue.trim());
-      setRenamingId(null);
-      setRenameValue('');
+      try {
+        await renamePreset(renamingId, renameValue.trim());
+        setRenamingId(null);
+        setRenameValue('');
+      } catch (e) {
+        console.error(e);
+        showToast({ message: presetErrorMessage(e, 'Failed to rename preset'), type: 'error' });
+      }
     } else {
       setRenamingId(null);
     }
@@ -76,8 +91,21 @@ export function PresetsPage() {
     e.stopPropagation();
     const name = window.prompt("Enter new name for cloned preset:", `${preset.name} (Copy)`);
     if (name && name.trim()) {
-      await duplicatePreset(preset.id, name.trim());
-      showToast({ message: 'Preset cloned', type: 'success' });
+      try {
+        await duplicatePreset(preset.id, name.trim());
+        showToast({ message: 'Preset cloned', type: 'success' });
+      } catch (e) {
+        console.error(e);
+        showToast({ message: presetErrorMessage(e, 'Failed to clone preset'), type: 'error' });
+      }
+    }
+  };
+
+  const handleResetPreset = async (preset: AnchorPreset, e: React.MouseEvent) => {
+    e.stopPropagation();
+    if (window.confirm(`Reset "${preset.name}" to its original anchors? Your changes to it will be lost.`)) {
+      await resetBuiltinPreset(preset.id);
+      showToast({ message: 'Preset reset to defaults', type: 'info' });
     }
   };
 
@@ -251,7 +279,15 @@ export function PresetsPage() {
                                        >
                                           [REN]
                                        </button>
-                                       {preset.id !== 1 && (
+                                       {preset.is_builtin ? (
+                                           <button
+                                              onClick={(e) => handleResetPreset(preset, e)}
+                                              className="bg-transparent border-0 px-1 text-[var(--nc-yellow)] hover:bg-[var(--nc-yellow)] hover:text-black text-xs font-mono"
+                                              title="Reset to factory defaults"
+                                            >
+                                              [RST]
+                                            </button>
+                                       ) : (
                                            <button
                                               onClick={(e) => handleDeletePreset(preset, e)}
                                               className="bg-transparent border-0 px-1 text-[var(--nc-yellow)] hover:bg-[var(--nc-yellow)] hover:text-black text-xs font-mono"
@@ -2,6 +2,7 @@ import { create } from 'zustand';
 import type { Chat, ChatWithPreview, Message } from '../lib/types';
 import * as api from '../lib/tauri';
 import { truncateAtWordBoundary } from '../lib/utils';
+import { useSettingsStore } from './settingsStore';
 
 export interface ChatError {
   message: string;
@@ -15,6 +16,7 @@ interface ChatState {
   messages: Message[];
   isLoading: boolean;
   streamingContent: string;
+  streamingReasoning: string;
   isStreaming: boolean;
   pendingRegenerate: boolean;
   error: ChatError | null;
@@ -29,6 +31,7 @@ interface ChatState {
   addMessage: (role: string, content: string) => Promise<Message | undefined>;
   editMessage: (messageId: number, content: string) => Promise<void>;
   appendStreamingContent: (token: string) => void;
+  appendStreamingReasoning: (text: string) => void;
   finalizeStreaming: () => Promise<void>;
   setIsStreaming: (streaming: boolean) => void;
   clearStreaming: () => void;
@@ -45,6 +48,7 @@ export const useChatStore = create<ChatState>((set, get) => ({
   messages: [],
   isLoading: false,
   streamingContent: '',
+  streamingReasoning: '',
   isStreaming: false,
   pendingRegenerate: false,
   error: null,
@@ -105,20 +109,39 @@ export const useChatStore = create<ChatState>((set, get) => ({
     set((state) => ({ streamingContent: state.streamingContent + token }));
   },
 
+  appendStreamingReasoning: (text: string) => {
+    set((state) => ({ streamingReasoning: state.streamingReasoning + text }));
+  },
+
   finalizeStreaming: async () => {
-    const { activeChatId, streamingContent } = get();
+    const { activeChatId, streamingContent, streamingReasoning } = get();
     if (!activeChatId || !streamingContent) return;
-    const message = await api.addMessage(activeChatId, 'assistant', streamingContent);
+    const message = await api.addMessage(activeChatId, 'assistant', streamingContent, streamingReasoning || undefined);
     set((state) => ({
       messages: [...state.messages, message],
       streamingContent: '',
+      streamingReasoning: '',
       isStreaming: false,
     }));
     await get().loadChats();
+
+    // Name the chat once the first exchange is complete
+    const exchanged = get().messages.filter(m => m.role !== 'system');
+    if (exchanged.length === 2) {
+      const { config } = useSettingsStore.getState();
+      api.generateChatTitle({
+        chatId: activeChatId,
+        providerUrl: config.providerUrl,
+        apiKey: config.apiKey,
+        model: config.model,
+      })
+        .then(() => get().loadChats())
+        .catch(error => console.error('Title generation failed:', error));
+    }
   },
 
   setIsStreaming: (streaming: boolean) => set({ isStreaming: streaming }),
-  clearStreaming: () => set({ streamingContent: '', isStreaming: false }),
+  clearStreaming: () => set({ streamingContent: '', streamingReasoning: '', isStreaming: false }),
   setPendingRegenerate: (v: boolean) => set({ pendingRegenerate: v }),
   setError: (error: ChatError | null) => set({ error }),
   clearError: () => set({ error: null }),
@@ -41,6 +41,7 @@ interface MixerState {
   deletePreset: (presetId: number) => Promise<void>;
   renamePreset: (presetId: number, name: string) => Promise<void>;
   duplicatePreset: (presetId: number, newName: string) => Promise<void>;
+  resetBuiltinPreset: (presetId: number) => Promise<void>;
   saveCurrentToPreset: () => Promise<void>;
 
   // Session actions
@@ -232,6 +233,10 @@ export const useMixerStore = create<MixerState>((set, get) => ({
         displayX: x,
         displayY: y,
         D_influence: pa.influence_radius,
+        anchorId: pa.anchor_id,
+        kind: pa.kind,
+        examples: pa.examples,
+        items: pa.items,
       };
     });
     set({ anchors, activePresetId: presetId });
@@ -241,6 +246,7 @@ export const useMixerStore = create<MixerState>((set, get) => ({
   savePreset: async (name: string) => {
     const { anchors } = get();
     const newAnchors: NewPresetAnchor[] = anchors.map((a, i) => ({
+      anchor_id: a.anchorId ?? null,
       label: a.name,
       prompt: a.prompt,
       icon_small: a.iconSmall,
@@ -250,6 +256,9 @@ export const useMixerStore = create<MixerState>((set, get) => ({
       position_y: a.y,
       influence_radius: a.D_influence,
       sort_order: i,
+      kind: a.kind,
+      examples: a.examples,
+      items: a.items,
     }));
     await api.createPreset(name, newAnchors);
     await get().loadPresets();
@@ -268,20 +277,16 @@ export const useMixerStore = create<MixerState>((set, get) => ({
     await get().loadPresets();
   },
 
+  resetBuiltinPreset: async (presetId: number) => {
+    await api.resetBuiltinPreset(presetId);
+    if (get().activePresetId === presetId) {
+      await get().loadPresetAnchors(presetId);
+    }
+    await get().loadPresets();
+  },
+
   duplicatePreset: async (presetId: number, newName: string) => {
-    const presetAnchors = await api.getPresetAnchors(presetId);
-    const newAnchors: NewPresetAnchor[] = presetAnchors.map(pa => ({
-      label: pa.label,
-      prompt: pa.prompt,
-      icon_small: pa.icon_small,
-      icon_large: pa.icon_large,
-      color: pa.color,
-      position_x: pa.position_x,
-      position_y: pa.position_y,
-      influence_radius: pa.influence_radius,
-      sort_order: pa.sort_order,
-    }));
-    await api.createPreset(newName, newAnchors);
+    await api.duplicatePreset(presetId, newName);
     await get().loadPresets();
   },
 
@@ -293,6 +298,7 @@ export const useMixerStore = create<MixerState>((set, get) => ({
     if (!currentPreset) throw new Error("Preset not found");
 
     const newAnchors: NewPresetAnchor[] = anchors.map((a, i) => ({
+      anchor_id: a.anchorId ?? null,
       label: a.name,
       prompt: a.prompt,
       icon_small: a.iconSmall,
@@ -302,6 +308,9 @@ export const useMixerStore = create<MixerState>((set, get) => ({
       position_y: a.y,
       influence_radius: a.D_influence,
       sort_order: i,
+      kind: a.kind,
+      examples: a.examples,
+      items: a.items,
     }));
 
     await api.updatePreset(activePresetId, currentPreset.name, newAnchors);
@@ -427,6 +436,9 @@ export const useMixerStore = create<MixerState>((set, get) => ({
         label: a.name,
         prompt: a.prompt,
         weight: emotionValues[a.name] || 0,
+        kind: a.kind,
+        examples: a.examples,
+        items: a.items,
       }));
   },
 }));